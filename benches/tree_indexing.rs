@@ -0,0 +1,139 @@
+//! Indexing parents with many children, run with `cargo bench --bench tree_indexing`.
+//!
+//! `sibling_storage` compares the sorted `Vec` of children [ParentIndex] used to keep (inserting by binary search,
+//! unlinking with `retain`) against the `BTreeSet` it keeps now.
+//! `tree_indexing` runs the tree systems end to end, compare it between commits with
+//! `cargo bench --bench tree_indexing -- --save-baseline before` and `-- --baseline before`.
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use shipyard_app::tree::*;
+use shipyard_app::*;
+use std::collections::BTreeSet;
+
+const FAN_OUTS: [usize; 3] = [1_000, 4_000, 16_000];
+
+/// Moves and deletes per update
+const CHANGES: usize = 100;
+
+/// Prime, so stepping by it visits every index once for the fan outs above
+const STRIDE: usize = 7919;
+
+/// Evenly spaced keys in a scattered order, like children linked over time
+fn scattered_keys(count: usize) -> Vec<(Ordered, u64)> {
+    let keys =
+        OrderedRange::new(&Ordered::hinted(0), &Ordered::hinted(255)).evenly_spaced_between(count);
+    (0..count)
+        .map(|step| {
+            let idx = step * STRIDE % count;
+            (keys[idx].clone(), idx as u64)
+        })
+        .collect()
+}
+
+fn sibling_storage(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sibling_storage");
+    group.sample_size(10);
+    for fan_out in FAN_OUTS.iter().copied() {
+        let keys = scattered_keys(fan_out);
+        group.bench_with_input(BenchmarkId::new("vec", fan_out), &keys, |b, keys| {
+            b.iter(|| {
+                let mut children: Vec<(Ordered, u64)> = Vec::new();
+                for key in keys {
+                    let at = children.binary_search(key).unwrap_or_else(|at| at);
+                    children.insert(at, key.clone());
+                }
+                for (_, id) in keys {
+                    children.retain(|(_, child)| child != id);
+                }
+                black_box(children)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("btree_set", fan_out), &keys, |b, keys| {
+            b.iter(|| {
+                let mut children = BTreeSet::new();
+                for key in keys {
+                    children.insert(key.clone());
+                }
+                for key in keys {
+                    children.remove(key);
+                }
+                black_box(children)
+            })
+        });
+    }
+    group.finish();
+}
+
+/// A parent with `fan_out` children whose [ChildOf] are not indexed yet
+fn app_with_children(fan_out: usize) -> (App, Vec<EntityId>) {
+    let app = App::new();
+    let mut builder = AppBuilder::new(&app);
+    builder.add_plugin(TreePlugin::<Hierarchy>::default());
+    builder.finish();
+    let children = app.run(
+        |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+            let parent = entities.add_entity((), ());
+            scattered_keys(fan_out)
+                .into_iter()
+                .map(|(key, _)| {
+                    entities.add_entity(&mut vm_child_of, ChildOf::ordered(parent, key))
+                })
+                .collect::<Vec<_>>()
+        },
+    );
+    (app, children)
+}
+
+fn indexed_app_with_children(fan_out: usize) -> (App, Vec<EntityId>) {
+    let (app, children) = app_with_children(fan_out);
+    app.update();
+    (app, children)
+}
+
+fn tree_indexing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tree_indexing");
+    group.sample_size(10);
+    for fan_out in FAN_OUTS.iter().copied() {
+        group.bench_function(BenchmarkId::new("link_children", fan_out), |b| {
+            b.iter_batched(
+                || app_with_children(fan_out),
+                |(app, _)| app.update(),
+                BatchSize::PerIteration,
+            )
+        });
+
+        group.bench_function(BenchmarkId::new("move_children", fan_out), |b| {
+            b.iter_batched(
+                || indexed_app_with_children(fan_out),
+                |(app, children)| {
+                    app.run(|mut commands: UniqueViewMut<MoveCommands>| {
+                        commands.extend((0..CHANGES).map(|idx| MoveCmd {
+                            target: children[idx],
+                            place: MoveToPlace::After(children[fan_out - 1 - idx]),
+                        }))
+                    });
+                    app.update();
+                },
+                BatchSize::PerIteration,
+            )
+        });
+
+        group.bench_function(BenchmarkId::new("delete_children", fan_out), |b| {
+            b.iter_batched(
+                || indexed_app_with_children(fan_out),
+                |(app, children)| {
+                    app.run(|mut all_storages: AllStoragesViewMut| {
+                        for child in children.iter().take(CHANGES) {
+                            all_storages.delete_entity(*child);
+                        }
+                    });
+                    app.update();
+                },
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, sibling_storage, tree_indexing);
+criterion_main!(benches);
@@ -1,8 +1,13 @@
-use std::any::{type_name, TypeId};
+use std::{
+    any::{type_name, TypeId},
+    borrow::Cow,
+    collections::HashSet,
+    sync::{Mutex, RwLock},
+};
 
 use crate::{
-    app_builder::AppBuilder, type_names::TypeNames, AppWorkload, AppWorkloadInfo, Plugin,
-    TypeIdBuckets,
+    app_builder::AppBuilder, type_names::TypeNames, AppBuildError, AppWorkload, AppWorkloadInfo,
+    Plugin, TypeIdBuckets,
 };
 use shipyard::*;
 use tracing::trace_span;
@@ -13,6 +18,10 @@ pub struct App {
     pub world: World,
     pub(crate) type_names: TypeNames,
     workload_ids: TypeIdBuckets<()>,
+    /// set when the default [AppBuilder] finishes, so [App::update] runs its stages
+    default_workload: RwLock<Option<AppWorkload>>,
+    /// startup workloads which have already been run
+    startups_run: Mutex<HashSet<Cow<'static, str>>>,
 }
 
 impl App {
@@ -26,6 +35,8 @@ impl App {
             world,
             workload_ids: TypeIdBuckets::new("Count times workload plugin added", &type_names),
             type_names,
+            default_workload: RwLock::new(None),
+            startups_run: Mutex::new(HashSet::new()),
         }
     }
 
@@ -39,6 +50,41 @@ impl App {
 
     #[track_caller]
     pub fn add_plugin_workload_with_info<P>(&mut self, plugin: P) -> (AppWorkload, AppWorkloadInfo)
+    where
+        P: Plugin + 'static,
+    {
+        let (name, workload_type_id, builder) = self.build_plugin_workload(plugin);
+        builder.finish_with_info_named(name, workload_type_id)
+    }
+
+    /// Like [App::add_plugin_workload], but returns every [AppBuildError] encountered instead of panicking.
+    #[track_caller]
+    pub fn try_add_plugin_workload<P>(
+        &mut self,
+        plugin: P,
+    ) -> Result<AppWorkload, Vec<AppBuildError>>
+    where
+        P: Plugin + 'static,
+    {
+        self.try_add_plugin_workload_with_info(plugin)
+            .map(|(workload, _)| workload)
+    }
+
+    /// Like [App::add_plugin_workload_with_info], but returns every [AppBuildError] encountered instead of panicking.
+    #[track_caller]
+    pub fn try_add_plugin_workload_with_info<P>(
+        &mut self,
+        plugin: P,
+    ) -> Result<(AppWorkload, AppWorkloadInfo), Vec<AppBuildError>>
+    where
+        P: Plugin + 'static,
+    {
+        let (name, workload_type_id, builder) = self.build_plugin_workload(plugin);
+        builder.try_finish_with_info_named(name, workload_type_id)
+    }
+
+    #[track_caller]
+    fn build_plugin_workload<P>(&mut self, plugin: P) -> (Cow<'static, str>, TypeId, AppBuilder<'_>)
     where
         P: Plugin + 'static,
     {
@@ -46,13 +92,13 @@ impl App {
         let workload_type_id = TypeId::of::<P>();
         let span = trace_span!("add_plugin_workload_with_info", plugin = ?workload_name);
         let _span = span.enter();
-        let name: std::borrow::Cow<'static, str> = match self.workload_ids.associate_type::<P>(()) {
+        let name: Cow<'static, str> = match self.workload_ids.associate_type::<P>(()) {
             crate::AssociateResult { nth } if nth == 1 => workload_name.into(),
             crate::AssociateResult { nth } => format!("{}_{}", workload_name, nth).into(),
         };
-        let mut builder = AppBuilder::new(&self);
-        plugin.build(&mut builder);
-        builder.finish_with_info_named(name, workload_type_id)
+        let mut builder = AppBuilder::new(self);
+        builder.add_plugin(plugin);
+        (name, workload_type_id, builder)
     }
 
     /// Runs default workload
@@ -60,7 +106,23 @@ impl App {
     pub fn update(&self) {
         let span = trace_span!("update");
         let _span = span.enter();
-        self.world.run_default().unwrap();
+        let default_workload = self.default_workload.read().expect("all mine!").clone();
+        match default_workload {
+            Some(workload) => workload.run(self),
+            None => self.world.run_default().unwrap(),
+        }
+    }
+
+    pub(crate) fn set_default_workload(&self, workload: AppWorkload) {
+        *self.default_workload.write().expect("all mine!") = Some(workload);
+    }
+
+    /// Returns true only the first time it is asked about this startup workload
+    pub(crate) fn is_first_run_of(&self, startup_workload: &Cow<'static, str>) -> bool {
+        self.startups_run
+            .lock()
+            .expect("all mine!")
+            .insert(startup_workload.clone())
     }
 
     #[track_caller]
@@ -1,7 +1,8 @@
 use std::{any::TypeId, borrow::Cow, collections::HashSet, sync::Arc};
 
 use crate::{
-    App, AppWorkload, AppWorkloadInfo, PluginAssociated, TypeIdBuckets, WorkloadSignature,
+    app_builder::AppWorkloadStep, App, AppWorkload, AppWorkloadInfo, PluginAssociated,
+    TypeIdBuckets, WorkloadSignature,
 };
 
 /// Associations made by this workload which includes the list of plugins and their reasons associated.
@@ -70,6 +71,7 @@ impl App {
     ///
     /// Conflicts guarded against:
     ///  * Two different workloads require update_pack for the same storage
+    ///  * Two different workloads require tracking for the same unique
     pub fn add_cycle(
         &mut self,
         cycle: Vec<(AppWorkload, AppWorkloadInfo)>,
@@ -77,6 +79,11 @@ impl App {
         // to track the plugins added so far (so we can avoid them accidentally conflicting with themselves)
         let mut workload_plugins_added = HashSet::new();
         let mut names_checked = Vec::new();
+        let mut startup_steps = Vec::<AppWorkloadStep>::new();
+        let mut cumulative_update_packed = TypeIdBuckets::<CycleWorkloadAssociations>::new(
+            "update packed storages in workloads",
+            &self.type_names,
+        );
         let mut cumulative_tracked_uniques = TypeIdBuckets::<CycleWorkloadAssociations>::new(
             "tracked uniques in workloads",
             &self.type_names,
@@ -88,7 +95,7 @@ impl App {
         };
 
         for (
-            _workloads,
+            workload,
             AppWorkloadInfo {
                 name,
                 plugin_id,
@@ -117,6 +124,21 @@ impl App {
                     signature: signature.clone(),
                 });
 
+                // account for update packed storages
+                for ((update_packed_type, _), assoc) in signature.track_update_packed.entries() {
+                    if !assoc.is_empty() {
+                        // We need to account for these update_packs
+                        cumulative_update_packed.associate(
+                            update_packed_type,
+                            CycleWorkloadAssociations {
+                                plugins: assoc,
+                                workload: name.clone(),
+                                workload_plugin_id: plugin_id,
+                            },
+                        );
+                    }
+                }
+
                 // account for tracked uniques
                 for ((tracked_type, _), assoc) in signature.track_tracked_uniques.entries() {
                     if !assoc.is_empty() {
@@ -133,11 +155,31 @@ impl App {
                 }
             }
 
-            names_checked.push(name.clone());
+            // each stage of the workload runs in order, startups only once for the whole cycle
+            names_checked.extend(workload.names);
+            for startup in workload.startup {
+                if !startup_steps.iter().any(|step| step.name == startup.name) {
+                    startup_steps.push(startup);
+                }
+            }
         }
 
         let mut errs = Vec::<CycleCheckError>::new();
 
+        // update pack
+        errs.extend(
+            cumulative_update_packed
+                .entries()
+                .into_iter()
+                .filter(|((_, _), workloads_dependent)| workloads_dependent.len() > 1)
+                .map(|((_, update_packed_storage_name), workloads_dependent)| {
+                    CycleCheckError::UpdatePackResetInMultipleWorkloads {
+                        update_pack: update_packed_storage_name,
+                        conflicts: workloads_dependent,
+                    }
+                }),
+        );
+
         // tracked unique
         errs.extend(
             cumulative_tracked_uniques
@@ -158,6 +200,7 @@ impl App {
 
         Ok((
             AppWorkload {
+                startup: startup_steps,
                 names: names_checked,
             },
             summary,
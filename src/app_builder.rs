@@ -1,18 +1,30 @@
 use crate::{
-    app::App, plugin::Plugin, tracked_unique::reset_tracked_unique, type_names::TypeNames,
+    app::App,
+    plugin::Plugin,
+    run_condition::RunCondition,
+    tracked_unique::{reset_tracked_unique, snapshot_previous, Previous, RESET_TRACKED_LABEL},
+    type_names::TypeNames,
 };
 use shipyard::*;
 use std::{
     any::{type_name, TypeId},
     borrow::Cow,
     collections::hash_map::Entry,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::Arc,
 };
 use tracing::*;
 
+mod build_error;
+mod plugin_dependencies;
 mod plugin_id;
-use plugin_id::PluginId;
+mod stage;
+mod system_order;
+pub use build_error::AppBuildError;
+pub use plugin_id::PluginId;
+pub use stage::Stage;
+use system_order::AppSystem;
+pub use system_order::SystemConfig;
 
 /// Used when a workload is created without a plugin
 pub static DEFAULT_WORKLOAD_NAME: &str = "update";
@@ -148,12 +160,31 @@ impl TypeIdBuckets<PluginAssociated> {
         };
         self.associate(type_id, assoc)
     }
+
+    /// Like [TypeIdBuckets::associate_plugin], for types only known by their [TypeId] and name
+    pub(crate) fn associate_plugin_type(
+        &mut self,
+        type_id: TypeId,
+        type_name: &'static str,
+        plugin: &PluginId,
+        reason: &'static str,
+    ) -> AssociateResult {
+        let type_id = self.track_type_names.track_name(type_id, type_name);
+        let assoc = PluginAssociated {
+            plugin: plugin.clone(),
+            reason,
+        };
+        self.associate(type_id, assoc)
+    }
+}
+
+fn unique_exists<T: Send + Sync + Component>(world: &World) -> bool {
+    world.borrow::<UniqueView<T>>().is_ok()
 }
 
 #[derive(Debug)]
 pub(crate) struct WorkloadSignature {
     /// track the plugins directly required by other plugins
-    #[allow(unused)]
     pub track_plugin_dependencies: PluginsAssociatedMap,
     /// unique type id to list of plugin type ids that provided a value for it it
     pub track_uniques_provided: PluginsAssociatedMap,
@@ -164,6 +195,8 @@ pub(crate) struct WorkloadSignature {
     pub track_update_packed: PluginsAssociatedMap,
     /// tracked uniques storage type id to list of (plugin type id, reason string)
     pub track_tracked_uniques: PluginsAssociatedMap,
+    /// tracked uniques storage type id to list of (plugin type id, reason string) for plugins reading [Previous] values
+    pub track_previous_uniques: PluginsAssociatedMap,
 }
 
 impl WorkloadSignature {
@@ -193,6 +226,10 @@ impl WorkloadSignature {
                 "Plugin requires tracked unique",
                 &type_names,
             ),
+            track_previous_uniques: PluginsAssociatedMap::new(
+                "Plugin requires previous value of tracked unique",
+                &type_names,
+            ),
         }
     }
 }
@@ -200,16 +237,27 @@ impl WorkloadSignature {
 /// Configure [App]s using the builder pattern
 pub struct AppBuilder<'a> {
     pub app: &'a App,
-    resets: Vec<WorkloadSystem>,
-    systems: Vec<WorkloadSystem>,
+    /// stages run every update, in order
+    stages: Vec<Stage>,
+    systems: Vec<AppSystem>,
+    /// plugins added, but waiting for their dependencies to be built
+    pending_plugins: Vec<plugin_dependencies::PendingPlugin>,
     /// track the plugins previously added to enable checking that plugin peer dependencies are satisified
     track_added_plugins: HashMap<TypeId, PluginId>,
+    /// track the plugins which have been built
+    track_built_plugins: HashSet<TypeId>,
     /// track the currently being used plugin ([PluginId] is a stack since some plugins add other plugins creating a nest)
     // TODO: Track "Plugin"s for each thing
     track_current_plugin: PluginId,
+    /// conditions of the plugins currently being built, applied to every system they add
+    track_current_conditions: Vec<RunCondition>,
     /// take a record of type names as we come across them for diagnostics
     track_type_names: TypeNames,
+    /// unique type id to a check for whether the unique is already present in the [World]
+    track_unique_exists: HashMap<TypeId, fn(&World) -> bool>,
     signature: WorkloadSignature,
+    /// problems encountered while building, reported by [AppBuilder::try_finish]
+    errors: Vec<AppBuildError>,
 }
 
 impl<'a> AppBuilder<'a> {
@@ -220,20 +268,49 @@ impl<'a> AppBuilder<'a> {
 
 #[derive(Clone, Debug)]
 pub struct AppWorkload {
-    pub(crate) names: Vec<std::borrow::Cow<'static, str>>,
+    /// workloads run only the first time this workload is run
+    pub(crate) startup: Vec<AppWorkloadStep>,
+    /// workloads run every time, in order
+    pub(crate) names: Vec<AppWorkloadStep>,
+}
+
+/// A shipyard workload run by an [AppWorkload] when all its conditions hold.
+#[derive(Clone, Debug)]
+pub(crate) struct AppWorkloadStep {
+    pub(crate) name: Cow<'static, str>,
+    pub(crate) conditions: Vec<RunCondition>,
+}
+
+impl AppWorkloadStep {
+    fn run(&self, app: &App) {
+        let workload_name = &self.name;
+        if let Some(condition) = self
+            .conditions
+            .iter()
+            .find(|condition| !condition.check(&app.world))
+        {
+            trace!(?workload_name, condition = condition.name(), "skipped");
+            return;
+        }
+
+        let span = trace_span!("AppWorkload::run", ?workload_name);
+        let _span = span.enter();
+        app.world.run_workload(workload_name).unwrap();
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct AppWorkloadInfo {
     #[allow(unused)]
     pub(crate) type_names: Blind<TypeNames>,
+    /// Batches of every stage's workloads, in the order they run
     #[allow(unused)]
     pub(crate) batch_info: Vec<info::BatchInfo>,
     /// Self-imposed constraints declared by the workload
     pub(crate) signature: Arc<WorkloadSignature>,
     /// Derived from this plugin
     pub(crate) plugin_id: TypeId,
-    /// Workload name assigned in the world
+    /// Name of the update stage's workload, each other stage runs as its own workload
     pub name: Cow<'static, str>,
 }
 
@@ -250,10 +327,13 @@ impl AppWorkload {
     #[track_caller]
     #[instrument(skip(app))]
     pub fn run(&self, app: &App) {
-        for workload_name in self.names.iter() {
-            let span = trace_span!("AppWorkload::run", ?workload_name);
-            let _span = span.enter();
-            app.world.run_workload(&workload_name).unwrap();
+        for step in self.startup.iter() {
+            if app.is_first_run_of(&step.name) {
+                step.run(app);
+            }
+        }
+        for step in self.names.iter() {
+            step.run(app);
         }
         let mut all_storages = app.world.borrow::<AllStoragesViewMut>().unwrap();
         all_storages.clear_all_removed_or_deleted();
@@ -271,61 +351,222 @@ impl<'a> AppBuilder<'a> {
     ///
     /// # Panics
     /// May panic if there are unmet unique dependencies or if there is an error adding workloads to shipyard.
+    /// See [AppBuilder::try_finish] for a non-panicking version.
     #[track_caller]
     pub fn finish(self) -> AppWorkload {
         self.finish_with_info().0
     }
 
+    /// Like [AppBuilder::finish], but returns every [AppBuildError] encountered while building instead of panicking.
+    #[track_caller]
+    pub fn try_finish(self) -> Result<AppWorkload, Vec<AppBuildError>> {
+        let app = self.app;
+        let (workload, _) = self.try_finish_with_info_named(
+            DEFAULT_WORKLOAD_NAME.into(),
+            std::any::TypeId::of::<DefaultWorkloadPlugin>(),
+        )?;
+        app.set_default_workload(workload.clone());
+        Ok(workload)
+    }
+
     /// Finish [App] and report back each of the update stages with their [AppWorkloadInfo].
     #[track_caller]
     fn finish_with_info(self) -> (AppWorkload, AppWorkloadInfo) {
-        self.finish_with_info_named(
+        let app = self.app;
+        let finished = self.finish_with_info_named(
             DEFAULT_WORKLOAD_NAME.into(),
             std::any::TypeId::of::<DefaultWorkloadPlugin>(),
-        )
+        );
+        app.set_default_workload(finished.0.clone());
+        finished
     }
 
     /// Finish [App] and report back each of the update stages with their [AppWorkloadInfo].
     #[track_caller]
-    #[instrument(skip(self))]
     pub(crate) fn finish_with_info_named(
         self,
         update_stage: std::borrow::Cow<'static, str>,
         plugin_id: TypeId,
     ) -> (AppWorkload, AppWorkloadInfo) {
+        match self.try_finish_with_info_named(update_stage, plugin_id) {
+            Ok(finished) => finished,
+            Err(errors) => build_error::panic_with_errors(&errors),
+        }
+    }
+
+    /// Finish [App] and report back each of the update stages with their [AppWorkloadInfo], or every [AppBuildError] encountered.
+    #[track_caller]
+    #[instrument(skip(self))]
+    pub(crate) fn try_finish_with_info_named(
+        mut self,
+        update_stage: std::borrow::Cow<'static, str>,
+        plugin_id: TypeId,
+    ) -> Result<(AppWorkload, AppWorkloadInfo), Vec<AppBuildError>> {
+        self.build_pending_plugins();
+        self.check_plugin_dependencies();
+
         let AppBuilder {
             app,
-            resets,
+            stages,
             systems,
+            pending_plugins: _,
             track_added_plugins: _,
+            track_built_plugins: _,
             track_current_plugin: _,
+            track_current_conditions: _,
             track_type_names: _,
+            track_unique_exists,
             signature,
+            mut errors,
         } = self;
 
-        let mut update_workload = systems.into_iter().fold(
-            WorkloadBuilder::new(update_stage.clone()),
-            |acc: WorkloadBuilder, system: WorkloadSystem| acc.with_system(system),
-        );
+        // every unique dependency must be provided by a plugin or already be in the world
+        for ((unique_type_id, unique_name), dependents) in
+            signature.track_unique_dependencies.entries()
+        {
+            let is_provided = signature
+                .track_uniques_provided
+                .type_plugins_lookup
+                .contains_key(&unique_type_id)
+                || signature
+                    .track_tracked_uniques_provided
+                    .type_plugins_lookup
+                    .contains_key(&unique_type_id)
+                || track_unique_exists
+                    .get(&unique_type_id)
+                    .map_or(false, |exists| exists(&app.world));
+
+            if !is_provided {
+                errors.push(AppBuildError::MissingUniqueDependency {
+                    unique: unique_name,
+                    dependents,
+                });
+            }
+        }
 
-        for reset_system in resets {
-            update_workload = update_workload.with_system(reset_system);
+        // group systems by stage, keeping the order they were added in
+        let mut staged_systems = HashMap::<Stage, Vec<AppSystem>>::new();
+        for system in systems {
+            let stage = system.config.stage;
+            if stage != Stage::Startup && !stages.contains(&stage) {
+                errors.push(AppBuildError::UnknownStage {
+                    stage: stage.name(),
+                    plugin: system.plugin,
+                });
+                continue;
+            }
+            staged_systems.entry(stage).or_default().push(system);
         }
 
-        let info = update_workload.add_to_world(&app.world).unwrap();
-        (
-            AppWorkload {
-                names: vec![update_stage],
-                // signature: Rc::new(signature),
-            },
+        let mut ordered_stages = Vec::new();
+        for stage in std::iter::once(Stage::Startup).chain(stages.iter().copied()) {
+            let systems = staged_systems.remove(&stage).unwrap_or_default();
+            match system_order::order_systems(systems) {
+                Ok(systems) => ordered_stages.push((stage, systems)),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        // systems sharing conditions are kept in one workload, so a stage becomes a workload per run of shared conditions
+        let mut steps = Vec::<(Stage, AppWorkloadStep)>::new();
+        let mut builders = Vec::<Option<WorkloadBuilder>>::new();
+        for (stage, systems) in ordered_stages {
+            // the update stage is always added, so it is known to shipyard even without systems
+            if systems.is_empty() && stage != Stage::Update {
+                continue;
+            }
+
+            let stage_name: Cow<'static, str> = if stage == Stage::Update {
+                update_stage.clone()
+            } else {
+                format!("{}::{}", update_stage, stage).into()
+            };
+
+            // ordering constraints also start a new workload, see order_systems
+            let mut segments = Vec::<(Vec<RunCondition>, Vec<AppSystem>)>::new();
+            for group in systems {
+                let mut starts_group = true;
+                for system in group {
+                    match segments.last_mut() {
+                        Some((conditions, segment))
+                            if !starts_group
+                                && RunCondition::same_conditions(
+                                    conditions,
+                                    &system.config.conditions,
+                                ) =>
+                        {
+                            segment.push(system)
+                        }
+                        _ => segments.push((system.config.conditions.clone(), vec![system])),
+                    }
+                    starts_group = false;
+                }
+            }
+            if segments.is_empty() {
+                segments.push((Vec::new(), Vec::new()));
+            }
+
+            for (nth, (conditions, systems)) in segments.into_iter().enumerate() {
+                let workload_name: Cow<'static, str> = if nth == 0 {
+                    stage_name.clone()
+                } else {
+                    format!("{}#{}", stage_name, nth).into()
+                };
+                builders.push(Some(systems.into_iter().fold(
+                    WorkloadBuilder::new(workload_name.clone()),
+                    |acc: WorkloadBuilder, system: AppSystem| acc.with_system(system.system),
+                )));
+                steps.push((
+                    stage,
+                    AppWorkloadStep {
+                        name: workload_name,
+                        conditions,
+                    },
+                ));
+            }
+        }
+
+        // add the update stage first, so it remains shipyard's default workload like before stages
+        let mut add_order = (0..steps.len()).collect::<Vec<_>>();
+        add_order.sort_by_key(|&idx| steps[idx].1.name != update_stage);
+        let mut step_infos = (0..steps.len()).map(|_| None).collect::<Vec<_>>();
+        for idx in add_order {
+            let builder = builders[idx].take().expect("each workload is added once");
+            let info = builder.add_to_world(&app.world).map_err(|error| {
+                vec![AppBuildError::AddWorkload {
+                    workload: steps[idx].1.name.clone(),
+                    error,
+                }]
+            })?;
+            step_infos[idx] = Some(info);
+        }
+        // batches of every step, startup included, in the order the steps run
+        let batch_info = step_infos
+            .into_iter()
+            .flat_map(|info| info.expect("each workload is added once").batch_info)
+            .collect();
+
+        let (startup, names) = steps
+            .into_iter()
+            .partition::<Vec<_>, _>(|(stage, _)| *stage == Stage::Startup);
+        let workload = AppWorkload {
+            startup: startup.into_iter().map(|(_, step)| step).collect(),
+            names: names.into_iter().map(|(_, step)| step).collect(),
+        };
+        Ok((
+            workload,
             AppWorkloadInfo {
-                batch_info: info.batch_info,
+                batch_info,
                 type_names: Blind(app.type_names.clone()),
                 plugin_id,
-                name: info.name,
+                name: update_stage,
                 signature: Arc::new(signature),
             },
-        )
+        ))
     }
 
     /// Lookup the type id while simultaneously storing the type name to be referenced later
@@ -333,7 +574,10 @@ impl<'a> AppBuilder<'a> {
         self.track_type_names.tracked_type_id_of::<T>()
     }
 
-    /// Update component `T`'s storage to be update_pack, and add [shipyard::sparse_set::SparseSet::clear_all_inserted_and_modified] as the last system.
+    /// Update component `T`'s storage to be update_pack.
+    ///
+    /// Inserted and modified components are not cleared by the builder: clear them once handled, like the tree_indexing system does,
+    /// or add [reset_tracked_storage](crate::reset_tracked_storage) with [AppBuilder::add_reset_system] to clear them at the end of every update.
     #[track_caller]
     pub fn update_pack<T: Component<Tracking = track::All> + Send + Sync>(
         &mut self,
@@ -345,13 +589,20 @@ impl<'a> AppBuilder<'a> {
             .associate_plugin::<T>(&self.track_current_plugin, reason)
             .is_first()
         {
-            self.app.world.borrow::<ViewMut<T>>().unwrap();
+            if let Err(error) = self.app.world.borrow::<ViewMut<T>>() {
+                self.errors.push(AppBuildError::UpdatePack {
+                    storage: type_name::<T>(),
+                    plugin: self.track_current_plugin.clone(),
+                    reason,
+                    error,
+                });
+            }
         }
 
         self
     }
 
-    /// Declare dependency on `[crate::Tracked]<T>` and add "reset tracked" as the last system.
+    /// Declare dependency on [Tracked](crate::Tracked)`<T>` and add "reset tracked" as the last system.
     #[track_caller]
     pub fn tracks<T: Component<Tracking = track::All> + Send + Sync>(
         &mut self,
@@ -363,10 +614,39 @@ impl<'a> AppBuilder<'a> {
             .associate_plugin::<T>(&self.track_current_plugin, reason)
             .is_first()
         {
-            self.resets.push(
-                reset_tracked_unique::<T>
-                    .into_workload_system()
-                    .expect("system to be valid"),
+            // resets must run even when the plugin's conditions do not hold
+            self.push_system(
+                reset_tracked_unique::<T>,
+                SystemConfig::labeled(RESET_TRACKED_LABEL).in_stage(Stage::Reset),
+            );
+        }
+
+        self
+    }
+
+    /// Like [AppBuilder::tracks], and also keep a [Previous] snapshot of `T` taken whenever tracking is reset.
+    ///
+    /// The [Previous] unique must be provided, e.g. by [TrackedUniquePlugin](crate::TrackedUniquePlugin).
+    /// Read both values with the [TrackedChange](crate::TrackedChange) view.
+    #[track_caller]
+    pub fn tracks_previous<T: Clone + Component<Tracking = track::All> + Send + Sync>(
+        &mut self,
+        reason: &'static str,
+    ) -> &mut Self {
+        self.tracks::<T>(reason)
+            .depends_on_unique::<Previous<T>>(reason);
+        if self
+            .signature
+            .track_previous_uniques
+            .associate_plugin::<T>(&self.track_current_plugin, reason)
+            .is_first()
+        {
+            // snapshot before tracking is cleared, so a change is not missed
+            self.push_system(
+                snapshot_previous::<T>,
+                SystemConfig::new()
+                    .in_stage(Stage::Reset)
+                    .before(RESET_TRACKED_LABEL),
             );
         }
 
@@ -398,7 +678,7 @@ impl<'a> AppBuilder<'a> {
 
     /// Add a tracked unique value.
     ///
-    /// Accessible through the [crate::Tracked] and [crate::TrackedMut] views.
+    /// Accessible through the [Tracked](crate::Tracked) and [TrackedMut](crate::TrackedMut) views, see [AppBuilder::tracks] to reset tracking after every update.
     #[track_caller]
     pub fn add_tracked_value<T: Component<Tracking = track::All>>(
         &mut self,
@@ -427,11 +707,15 @@ impl<'a> AppBuilder<'a> {
     /// Declare that this builder has a dependency on the following unique.
     ///
     /// If the unique dependency is not satisfied by the time [AppBuilder::finish] is called, then the finish call will panic.
+    /// The unique may be provided by any plugin in this builder, or already be present in the [App::world].
     #[track_caller]
     pub fn depends_on_unique<T>(&mut self, dependency_reason: &'static str) -> &mut Self
     where
         T: Send + Sync + Component,
     {
+        let unique_type_id = self.tracked_type_id_of::<T>();
+        self.track_unique_exists
+            .insert(unique_type_id, unique_exists::<T>);
         self.signature
             .track_unique_dependencies
             .associate_plugin::<T>(&self.track_current_plugin, dependency_reason);
@@ -440,44 +724,102 @@ impl<'a> AppBuilder<'a> {
     }
 
     /// Declare that this builder has a dependency on the following plugin.
+    ///
+    /// If the plugin is not added by the time [AppBuilder::finish] is called, then the finish call will panic.
+    /// Use [Plugin::dependencies] to also ensure the dependency is built first.
     #[track_caller]
     pub fn depends_on_plugin<T>(&mut self, dependency_reason: &'static str) -> &mut Self
     where
         T: Plugin,
     {
-        let plugin_type_id = self.tracked_type_id_of::<T>();
-        if !self.track_added_plugins.contains_key(&plugin_type_id) {
-            panic!(
-                "\"{}\" depends on \"{}\": {}",
-                self.track_current_plugin,
+        self.signature
+            .track_plugin_dependencies
+            .associate_plugin_type(
+                TypeId::of::<T>(),
                 type_name::<T>(),
-                dependency_reason
+                &self.track_current_plugin,
+                dependency_reason,
             );
-        }
         self
     }
 
     fn empty(app: &App) -> AppBuilder<'_> {
         AppBuilder {
             app,
-            resets: Vec::new(),
+            stages: Stage::default_order(),
             systems: Vec::new(),
+            pending_plugins: Vec::new(),
             track_added_plugins: Default::default(),
+            track_built_plugins: Default::default(),
             track_current_plugin: Default::default(),
+            track_current_conditions: Vec::new(),
             track_type_names: Default::default(),
+            track_unique_exists: Default::default(),
             signature: WorkloadSignature::new(&app.type_names),
+            errors: Vec::new(),
         }
     }
 
     #[track_caller]
     pub fn add_system<B, R, S: IntoWorkloadSystem<B, R>>(&mut self, system: S) -> &mut Self {
-        self.systems
-            .push(system.into_workload_system().expect("system to be valid"));
+        self.add_system_with(system, SystemConfig::default())
+    }
+
+    /// Add a system with labels and ordering constraints relative to other systems in this builder.
+    ///
+    /// Contradicting constraints are reported when the builder is finished.
+    #[track_caller]
+    pub fn add_system_with<B, R, S: IntoWorkloadSystem<B, R>>(
+        &mut self,
+        system: S,
+        mut config: SystemConfig,
+    ) -> &mut Self {
+        // conditions of the plugin(s) adding this system come first
+        config
+            .conditions
+            .splice(0..0, self.track_current_conditions.iter().cloned());
+        self.push_system(system, config)
+    }
+
+    /// Add a system which only runs when `condition` holds.
+    #[track_caller]
+    pub fn add_system_if<B, R, S: IntoWorkloadSystem<B, R>>(
+        &mut self,
+        system: S,
+        condition: RunCondition,
+    ) -> &mut Self {
+        self.add_system_with(system, SystemConfig::new().run_if(condition))
+    }
+
+    #[track_caller]
+    fn push_system<B, R, S: IntoWorkloadSystem<B, R>>(
+        &mut self,
+        system: S,
+        config: SystemConfig,
+    ) -> &mut Self {
+        self.systems.push(AppSystem {
+            name: type_name::<S>(),
+            plugin: self.track_current_plugin.clone(),
+            system: system.into_workload_system().expect("system to be valid"),
+            config,
+        });
 
         self
     }
 
-    /// Ensure that this system is among the absolute last systems
+    /// Add a system to run during the given [Stage] instead of [Stage::Update].
+    #[track_caller]
+    pub fn add_system_to_stage<B, R, S: IntoWorkloadSystem<B, R>>(
+        &mut self,
+        stage: Stage,
+        system: S,
+    ) -> &mut Self {
+        self.add_system_with(system, SystemConfig::new().in_stage(stage))
+    }
+
+    /// Ensure that this system is among the absolute last systems (in [Stage::Reset])
+    ///
+    /// Like the resets added by [AppBuilder::tracks], it runs even when the conditions of the plugin adding it do not hold.
     #[track_caller]
     pub fn add_reset_system<B, R, S: IntoWorkloadSystem<B, R>>(
         &mut self,
@@ -485,14 +827,105 @@ impl<'a> AppBuilder<'a> {
         reason: &str,
     ) -> &mut Self {
         trace!(plugin = ?self.track_current_plugin, ?reason, "add_reset_system");
-        self.resets
-            .push(system.into_workload_system().expect("system to be valid"));
+        self.push_system(system, SystemConfig::new().in_stage(Stage::Reset))
+    }
+
+    /// Add a named stage to run just before another stage.
+    ///
+    /// Adding a stage which was already added by another plugin leaves it in place.
+    #[track_caller]
+    pub fn add_stage_before(&mut self, stage: &'static str, before: Stage) -> &mut Self {
+        self.insert_stage(stage, before, 0)
+    }
+
+    /// Add a named stage to run just after another stage, other than [Stage::Reset] which always runs last.
+    ///
+    /// Adding a stage which was already added by another plugin leaves it in place.
+    #[track_caller]
+    pub fn add_stage_after(&mut self, stage: &'static str, after: Stage) -> &mut Self {
+        self.insert_stage(stage, after, 1)
+    }
+
+    fn insert_stage(
+        &mut self,
+        stage: &'static str,
+        relative_to: Stage,
+        offset: usize,
+    ) -> &mut Self {
+        if self.stages.contains(&Stage::Named(stage)) {
+            return self;
+        }
+        if relative_to == Stage::Reset && offset > 0 {
+            self.errors.push(AppBuildError::StageAfterReset {
+                stage,
+                plugin: self.track_current_plugin.clone(),
+            });
+            return self;
+        }
+
+        match self
+            .stages
+            .iter()
+            .position(|existing| *existing == relative_to)
+        {
+            Some(idx) => self.stages.insert(idx + offset, Stage::Named(stage)),
+            None => self.errors.push(AppBuildError::UnknownStage {
+                stage: relative_to.name(),
+                plugin: self.track_current_plugin.clone(),
+            }),
+        }
 
         self
     }
 
+    /// Add a plugin to be built with this builder.
+    ///
+    /// Plugins are built when the builder is finished, each after the plugins declared in its [Plugin::dependencies].
+    /// A plugin added while building another plugin is built right away, so the other plugin can use what it added,
+    /// unless it depends on a plugin which is not built yet.
+    /// Problems adding the plugin are reported when the builder is finished, see [AppBuilder::try_add_plugin] to handle them immediately.
     #[track_caller]
     pub fn add_plugin<T>(&mut self, plugin: T) -> &mut Self
+    where
+        T: Plugin,
+    {
+        if let Err(err) = self.try_add_plugin(plugin) {
+            self.errors.push(err);
+        }
+        self
+    }
+
+    /// Add a plugin whose systems (and the systems of plugins it adds) only run when `condition` holds.
+    ///
+    /// Plugins added by default through [Plugin::dependencies] do not inherit the condition, since others may depend on them too.
+    #[track_caller]
+    pub fn add_plugin_if<T>(&mut self, plugin: T, condition: RunCondition) -> &mut Self
+    where
+        T: Plugin,
+    {
+        if let Err(err) = self.try_add_plugin_with_conditions(plugin, vec![condition]) {
+            self.errors.push(err);
+        }
+        self
+    }
+
+    /// Add a plugin to be built with this builder, or return why it could not be added.
+    ///
+    /// Errors from the plugin's own build (e.g. unmet dependencies) are still reported when the builder is finished.
+    #[track_caller]
+    pub fn try_add_plugin<T>(&mut self, plugin: T) -> Result<&mut Self, AppBuildError>
+    where
+        T: Plugin,
+    {
+        self.try_add_plugin_with_conditions(plugin, Vec::new())
+    }
+
+    #[track_caller]
+    fn try_add_plugin_with_conditions<T>(
+        &mut self,
+        plugin: T,
+        conditions: Vec<RunCondition>,
+    ) -> Result<&mut Self, AppBuildError>
     where
         T: Plugin,
     {
@@ -501,30 +934,37 @@ impl<'a> AppBuilder<'a> {
         let _span = span.enter();
         if let Some(plugin_id) = self.track_added_plugins.get(&plugin_type_id) {
             if !plugin.can_add_multiple_times() {
-                panic!(
-                    "Plugin ({}) cannot add plugin as it's already added as \"{}\". (Implement `Plugin::can_add_multiple_times` to override)",
-                    self.track_current_plugin, plugin_id
-                );
+                return Err(AppBuildError::DuplicatePlugin {
+                    plugin: type_name::<T>(),
+                    added_by: self.track_current_plugin.clone(),
+                    already_added_as: plugin_id.clone(),
+                });
             }
         }
 
         if self.track_current_plugin.contains(plugin_type_id) {
-            panic!(
-                "Plugin ({}) cannot add plugin ({}) as it would cause a cycle",
-                self.track_current_plugin,
-                self.track_type_names
-                    .lookup_name(&plugin_type_id)
-                    .unwrap_or(""),
-            );
+            return Err(AppBuildError::PluginCycle {
+                plugin: type_name::<T>(),
+                chain: self.track_current_plugin.clone(),
+            });
         }
 
-        self.track_current_plugin.push::<T>();
-        trace_span!("build", plugin = ?self.track_current_plugin).in_scope(|| {
-            plugin.build(self);
-        });
-        self.track_added_plugins
-            .insert(plugin_type_id, self.track_current_plugin.clone());
-        self.track_current_plugin.pop();
-        self
+        let mut plugin_id = self.track_current_plugin.clone();
+        plugin_id.push::<T>();
+        let mut plugin_conditions = self.track_current_conditions.clone();
+        plugin_conditions.extend(conditions);
+        let pending_before = self.pending_plugins.len();
+        self.register_plugin(
+            plugin_type_id,
+            Box::new(plugin),
+            plugin_id,
+            plugin_conditions,
+        );
+        if self.track_current_plugin.last().is_some() {
+            // added while building another plugin, which may use what it adds right away
+            let added = self.pending_plugins.len() - pending_before;
+            self.build_added_plugins(added);
+        }
+        Ok(self)
     }
 }
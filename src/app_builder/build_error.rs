@@ -0,0 +1,281 @@
+use std::borrow::Cow;
+
+use shipyard::error;
+
+use super::{PluginAssociated, PluginId};
+
+/// Problems found while building an [AppWorkload](crate::AppWorkload) from plugins.
+///
+/// Returned from [AppBuilder::try_finish](crate::AppBuilder::try_finish) and [AppBuilder::try_add_plugin](crate::AppBuilder::try_add_plugin),
+/// or reported in the panic message of their non-`try` counterparts.
+#[derive(Debug)]
+pub enum AppBuildError {
+    /// Plugin was added more than once without overriding [Plugin::can_add_multiple_times](crate::Plugin::can_add_multiple_times)
+    DuplicatePlugin {
+        plugin: &'static str,
+        /// The plugin chain attempting to add the plugin again
+        added_by: PluginId,
+        /// Where the plugin was first added
+        already_added_as: PluginId,
+    },
+    /// Plugin would end up adding itself through its own chain of plugins
+    PluginCycle {
+        plugin: &'static str,
+        chain: PluginId,
+    },
+    /// Plugins declared in [Plugin::dependencies](crate::Plugin::dependencies) depend on each other
+    PluginDependencyCycle { chain: PluginId },
+    /// Plugin declared a dependency on another plugin which was never added
+    MissingPluginDependency {
+        plugin: &'static str,
+        dependent: PluginId,
+        reason: &'static str,
+    },
+    /// Unique declared with [AppBuilder::depends_on_unique](crate::AppBuilder::depends_on_unique) was neither provided by a plugin nor present in the world
+    MissingUniqueDependency {
+        unique: &'static str,
+        /// Every plugin which declared the dependency, with its reason
+        dependents: Vec<PluginAssociated>,
+    },
+    /// Systems' `before` / `after` constraints contradict each other, see [SystemConfig](crate::SystemConfig)
+    SystemOrderCycle {
+        /// Each system name with the plugin which added it, in the order they would need to run
+        systems: Vec<(&'static str, PluginId)>,
+    },
+    /// Stage was never added with [AppBuilder::add_stage_before](crate::AppBuilder::add_stage_before) or [AppBuilder::add_stage_after](crate::AppBuilder::add_stage_after)
+    UnknownStage {
+        stage: &'static str,
+        /// The plugin which referred to the stage
+        plugin: PluginId,
+    },
+    /// [Stage::Reset](crate::Stage::Reset) runs after all other stages, so no stage can be added after it
+    StageAfterReset {
+        stage: &'static str,
+        /// The plugin which added the stage
+        plugin: PluginId,
+    },
+    /// Shipyard could not provide the storage requested by [AppBuilder::update_pack](crate::AppBuilder::update_pack)
+    UpdatePack {
+        storage: &'static str,
+        plugin: PluginId,
+        reason: &'static str,
+        error: error::GetStorage,
+    },
+    /// Shipyard refused the finished workload
+    AddWorkload {
+        workload: Cow<'static, str>,
+        error: error::AddWorkload,
+    },
+}
+
+impl std::fmt::Display for AppBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppBuildError::DuplicatePlugin {
+                plugin,
+                added_by,
+                already_added_as,
+            } => write!(
+                f,
+                "Plugin ({}) cannot add plugin ({}) as it's already added as \"{}\". (Implement `Plugin::can_add_multiple_times` to override)",
+                added_by, plugin, already_added_as
+            ),
+            AppBuildError::PluginCycle { plugin, chain } => write!(
+                f,
+                "Plugin ({}) cannot add plugin ({}) as it would cause a cycle",
+                chain, plugin
+            ),
+            AppBuildError::PluginDependencyCycle { chain } => {
+                write!(f, "Plugin dependencies form a cycle ({})", chain)
+            }
+            AppBuildError::MissingPluginDependency {
+                plugin,
+                dependent,
+                reason,
+            } => write!(f, "\"{}\" depends on \"{}\": {}", dependent, plugin, reason),
+            AppBuildError::MissingUniqueDependency { unique, dependents } => write!(
+                f,
+                "Unique ({}) is not provided, but is depended on by {:?}",
+                unique, dependents
+            ),
+            AppBuildError::SystemOrderCycle { systems } => {
+                f.write_str("System ordering constraints form a cycle: ")?;
+                for (idx, (system, plugin)) in systems.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(" → ")?;
+                    }
+                    write!(f, "{} ({})", system, plugin)?;
+                }
+                Ok(())
+            }
+            AppBuildError::UnknownStage { stage, plugin } => write!(
+                f,
+                "Plugin ({}) refers to stage ({}) which was never added",
+                plugin, stage
+            ),
+            AppBuildError::StageAfterReset { stage, plugin } => write!(
+                f,
+                "Plugin ({}) cannot add stage ({}) after the reset stage, which runs last",
+                plugin, stage
+            ),
+            AppBuildError::UpdatePack {
+                storage,
+                plugin,
+                reason,
+                error,
+            } => write!(
+                f,
+                "Plugin ({}) could not update_pack ({}) \"{}\": {:?}",
+                plugin, storage, reason, error
+            ),
+            AppBuildError::AddWorkload { workload, error } => write!(
+                f,
+                "Workload ({}) could not be added to the world: {:?}",
+                workload, error
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AppBuildError {}
+
+/// Panic with every error listed, used by the non-`try` builder methods.
+#[track_caller]
+pub(crate) fn panic_with_errors(errors: &[AppBuildError]) -> ! {
+    let listed = errors
+        .iter()
+        .map(|err| format!(" * {}", err))
+        .collect::<Vec<_>>()
+        .join("\n");
+    panic!("Failed to build app workload:\n{}", listed)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{App, AppBuildError, AppBuilder, Component, Plugin};
+
+    struct Inner;
+    struct AddsInnerTwice;
+    struct NeedsInner;
+    struct NeedsUniques;
+    struct ProvidesU1;
+
+    #[derive(Component)]
+    struct U1;
+    #[derive(Component)]
+    struct U2;
+
+    impl Plugin for Inner {
+        fn build(&self, _: &mut AppBuilder) {}
+    }
+
+    impl Plugin for AddsInnerTwice {
+        fn build(&self, app: &mut AppBuilder) {
+            app.add_plugin(Inner).add_plugin(Inner);
+        }
+    }
+
+    impl Plugin for NeedsInner {
+        fn build(&self, app: &mut AppBuilder) {
+            app.depends_on_plugin::<Inner>("needs inner to be set up");
+        }
+    }
+
+    impl Plugin for NeedsUniques {
+        fn build(&self, app: &mut AppBuilder) {
+            app.depends_on_unique::<U1>("reads U1")
+                .depends_on_unique::<U2>("reads U2");
+        }
+    }
+
+    impl Plugin for ProvidesU1 {
+        fn build(&self, app: &mut AppBuilder) {
+            app.add_unique(U1);
+        }
+    }
+
+    #[test]
+    fn try_add_plugin_reports_duplicate() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.try_add_plugin(Inner).expect("first add is ok");
+
+        match builder.try_add_plugin(Inner) {
+            Err(AppBuildError::DuplicatePlugin { plugin, .. }) => {
+                assert_eq!(plugin, std::any::type_name::<Inner>())
+            }
+            other => panic!("Expected DuplicatePlugin, but found {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn try_finish_reports_nested_errors() {
+        let mut app = App::new();
+
+        let errors = app
+            .try_add_plugin_workload(AddsInnerTwice)
+            .expect_err("expected duplicate plugin");
+        assert!(
+            matches!(errors.as_slice(), [AppBuildError::DuplicatePlugin { .. }]),
+            "Expected 1 DuplicatePlugin error, but found: {:#?}",
+            errors
+        );
+
+        let errors = app
+            .try_add_plugin_workload(NeedsInner)
+            .expect_err("expected missing dependency");
+        assert!(
+            matches!(
+                errors.as_slice(),
+                [AppBuildError::MissingPluginDependency {
+                    reason: "needs inner to be set up",
+                    ..
+                }]
+            ),
+            "Expected 1 MissingPluginDependency error, but found: {:#?}",
+            errors
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "depends on")]
+    fn finish_panics_with_errors() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(NeedsInner);
+        builder.finish();
+    }
+
+    #[test]
+    fn try_finish_reports_every_missing_unique() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(NeedsUniques);
+
+        let errors = builder.try_finish().expect_err("expected missing uniques");
+        let missing = errors
+            .iter()
+            .map(|err| match err {
+                AppBuildError::MissingUniqueDependency { unique, dependents } => {
+                    assert_eq!(dependents.len(), 1);
+                    *unique
+                }
+                other => panic!("Expected MissingUniqueDependency, but found {:?}", other),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            missing,
+            vec![std::any::type_name::<U1>(), std::any::type_name::<U2>()]
+        );
+    }
+
+    #[test]
+    fn unique_dependencies_satisfied_by_plugins_or_world() {
+        let app = App::new();
+        app.world.add_unique(U2).unwrap();
+
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(NeedsUniques).add_plugin(ProvidesU1);
+        builder.try_finish().expect("all uniques provided");
+    }
+}
@@ -0,0 +1,494 @@
+//! Resolves the order plugins are built in from their declared [Plugin::dependencies].
+use std::{any::TypeId, collections::HashSet, ops::Range};
+
+use tracing::*;
+
+use super::{AppBuildError, AppBuilder, PluginId};
+use crate::{Plugin, PluginDependency, RunCondition};
+
+/// A plugin which has been added, but not yet built.
+pub(crate) struct PendingPlugin {
+    type_id: TypeId,
+    plugin: Box<dyn Plugin>,
+    /// The chain of plugins which added this plugin, including itself
+    plugin_id: PluginId,
+    dependencies: Vec<PluginDependency>,
+    /// conditions applied to every system the plugin adds
+    conditions: Vec<RunCondition>,
+}
+
+impl AppBuilder<'_> {
+    /// Queue plugin to be built once its dependencies are built.
+    ///
+    /// Missing default-constructible dependencies are only added by [AppBuilder::build_pending_plugins],
+    /// so the dependency can still be added with its own configuration after the plugin depending on it.
+    pub(crate) fn register_plugin(
+        &mut self,
+        type_id: TypeId,
+        plugin: Box<dyn Plugin>,
+        plugin_id: PluginId,
+        conditions: Vec<RunCondition>,
+    ) {
+        self.track_added_plugins.insert(type_id, plugin_id.clone());
+
+        let dependencies = plugin.dependencies();
+        for dependency in dependencies.iter() {
+            if dependency.optional {
+                continue;
+            }
+
+            self.signature
+                .track_plugin_dependencies
+                .associate_plugin_type(
+                    dependency.type_id,
+                    dependency.name,
+                    &plugin_id,
+                    dependency.reason,
+                );
+        }
+
+        self.pending_plugins.push(PendingPlugin {
+            type_id,
+            plugin,
+            plugin_id,
+            dependencies,
+            conditions,
+        });
+    }
+
+    /// Build every pending plugin after the plugins it depends on.
+    ///
+    /// Plugins added while building are built in the same pass, see [AppBuilder::build_added_plugins].
+    /// Once nothing else can be built, missing default-constructible dependencies are added and built too.
+    pub(crate) fn build_pending_plugins(&mut self) {
+        loop {
+            while let Some(next) = self.next_buildable_plugin(0..self.pending_plugins.len()) {
+                self.build_pending_plugin(next);
+            }
+            if !self.add_default_dependencies() {
+                break;
+            }
+        }
+
+        // anything left over is either waiting on a missing plugin (reported with the other
+        // plugin dependencies) or waiting on itself through a cycle
+        let unbuilt = std::mem::take(&mut self.pending_plugins);
+        let mut reported = HashSet::<Vec<TypeId>>::new();
+        for start in 0..unbuilt.len() {
+            if let Some(cycle) = find_dependency_cycle(&unbuilt, start) {
+                let mut cycle_key = cycle
+                    .iter()
+                    .map(|idx| unbuilt[*idx].type_id)
+                    .collect::<Vec<_>>();
+                cycle_key.sort();
+                if reported.insert(cycle_key) {
+                    let mut chain = PluginId::default();
+                    for idx in cycle.iter().chain(cycle.first()) {
+                        if let Some((type_id, name)) = unbuilt[*idx].plugin_id.last() {
+                            chain.push_named(type_id, name);
+                        }
+                    }
+                    self.errors
+                        .push(AppBuildError::PluginDependencyCycle { chain });
+                }
+            }
+        }
+    }
+
+    /// Build the last `count` pending plugins, which were just added while building another plugin,
+    /// so that plugin can use what they add right away.
+    ///
+    /// Plugins waiting on a dependency which is not built yet are left to [AppBuilder::build_pending_plugins].
+    pub(crate) fn build_added_plugins(&mut self, mut count: usize) {
+        // plugins added by the builds below are pushed after these, and built by their own call
+        let first = self.pending_plugins.len() - count;
+        while let Some(next) = self.next_buildable_plugin(first..first + count) {
+            self.build_pending_plugin(next);
+            count -= 1;
+        }
+    }
+
+    /// Add the default of every dependency of the pending plugins which nobody added, returning whether any was added.
+    fn add_default_dependencies(&mut self) -> bool {
+        let missing = self
+            .pending_plugins
+            .iter()
+            .flat_map(|pending| {
+                pending.dependencies.iter().filter_map(move |dependency| {
+                    let default = dependency.default.filter(|_| !dependency.optional)?;
+                    let mut dependency_id = pending.plugin_id.clone();
+                    dependency_id.push_named(dependency.type_id, dependency.name);
+                    Some((dependency.type_id, default, dependency_id))
+                })
+            })
+            .filter(|(type_id, ..)| !self.track_added_plugins.contains_key(type_id))
+            .collect::<Vec<_>>();
+
+        let mut added = false;
+        for (type_id, default, dependency_id) in missing {
+            // the same dependency may be missing for several plugins
+            if self.track_added_plugins.contains_key(&type_id) {
+                continue;
+            }
+            trace!(adding = ?dependency_id, "add default dependency");
+            // shared dependencies do not inherit the conditions of the plugin which happened to add them
+            self.register_plugin(type_id, default(), dependency_id, Vec::new());
+            added = true;
+        }
+        added
+    }
+
+    fn build_pending_plugin(&mut self, idx: usize) {
+        let PendingPlugin {
+            type_id,
+            plugin,
+            plugin_id,
+            dependencies: _,
+            conditions,
+        } = self.pending_plugins.remove(idx);
+
+        let parent_plugin = std::mem::replace(&mut self.track_current_plugin, plugin_id);
+        let parent_conditions = std::mem::replace(&mut self.track_current_conditions, conditions);
+        trace_span!("build", plugin = ?self.track_current_plugin).in_scope(|| {
+            plugin.build(self);
+        });
+        self.track_current_plugin = parent_plugin;
+        self.track_current_conditions = parent_conditions;
+        self.track_built_plugins.insert(type_id);
+    }
+
+    /// Every plugin depended upon must have been added by now.
+    pub(crate) fn check_plugin_dependencies(&mut self) {
+        for ((plugin_type_id, plugin_name), dependents) in
+            self.signature.track_plugin_dependencies.entries()
+        {
+            if !self.track_added_plugins.contains_key(&plugin_type_id) {
+                for dependent in dependents {
+                    self.errors.push(AppBuildError::MissingPluginDependency {
+                        plugin: plugin_name,
+                        dependent: dependent.plugin,
+                        reason: dependent.reason,
+                    });
+                }
+            }
+        }
+    }
+
+    /// The first pending plugin within `range` (in the order added) with every dependency built.
+    fn next_buildable_plugin(&self, range: Range<usize>) -> Option<usize> {
+        let start = range.start;
+        self.pending_plugins[range]
+            .iter()
+            .position(|pending| {
+                pending.dependencies.iter().all(|dependency| {
+                    if self.track_built_plugins.contains(&dependency.type_id) {
+                        true
+                    } else if self.track_added_plugins.contains_key(&dependency.type_id) {
+                        // added, but waiting to be built
+                        false
+                    } else {
+                        // not added (yet), so only optional dependencies can be skipped
+                        dependency.optional
+                    }
+                })
+            })
+            .map(|idx| start + idx)
+    }
+}
+
+/// Follow dependencies between unbuilt plugins from `start`, returning the indexes forming a cycle.
+fn find_dependency_cycle(unbuilt: &[PendingPlugin], start: usize) -> Option<Vec<usize>> {
+    fn visit(
+        unbuilt: &[PendingPlugin],
+        current: usize,
+        path: &mut Vec<usize>,
+        visited: &mut HashSet<usize>,
+    ) -> Option<Vec<usize>> {
+        for dependency in unbuilt[current].dependencies.iter() {
+            if let Some(dependency_idx) = unbuilt
+                .iter()
+                .position(|pending| pending.type_id == dependency.type_id)
+            {
+                if let Some(cycle_start) = path.iter().position(|idx| *idx == dependency_idx) {
+                    return Some(path[cycle_start..].to_vec());
+                }
+                if visited.insert(dependency_idx) {
+                    path.push(dependency_idx);
+                    if let Some(cycle) = visit(unbuilt, dependency_idx, path, visited) {
+                        return Some(cycle);
+                    }
+                    path.pop();
+                }
+            }
+        }
+        None
+    }
+
+    visit(unbuilt, start, &mut vec![start], &mut HashSet::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{App, AppBuildError, AppBuilder, Plugin, PluginDependency};
+    use shipyard::{Component, UniqueView, UniqueViewMut};
+
+    #[derive(Component, Default)]
+    struct BuildOrder(Vec<&'static str>);
+
+    fn record_build(app: &AppBuilder, name: &'static str) {
+        app.app
+            .world
+            .run(|mut order: UniqueViewMut<BuildOrder>| order.0.push(name))
+            .unwrap();
+    }
+
+    fn build_order(app: &App) -> Vec<&'static str> {
+        app.run(|order: UniqueView<BuildOrder>| order.0.clone())
+    }
+
+    /// Provides its value, "provided by Base" by default
+    struct Base(&'static str);
+    struct Configured;
+    /// Depends on [Base] which can be added by default
+    struct NeedsBase;
+    /// Depends on [Configured] which must be added by someone else
+    struct NeedsConfigured;
+    struct MaybeConfigured;
+    struct CycleA;
+    struct CycleB;
+    /// Adds [Base] while building, and reads the unique it provides
+    struct AddsBase;
+    struct AddsNeedsConfigured;
+
+    impl Default for Base {
+        fn default() -> Self {
+            Base("provided by Base")
+        }
+    }
+
+    impl Plugin for Base {
+        fn build(&self, app: &mut AppBuilder) {
+            record_build(app, "Base");
+            app.add_unique(Provided(self.0));
+        }
+    }
+
+    impl Plugin for Configured {
+        fn build(&self, app: &mut AppBuilder) {
+            record_build(app, "Configured");
+        }
+    }
+
+    impl Plugin for NeedsBase {
+        fn build(&self, app: &mut AppBuilder) {
+            record_build(app, "NeedsBase");
+        }
+        fn dependencies(&self) -> Vec<PluginDependency> {
+            vec![PluginDependency::new::<Base>("builds on base")]
+        }
+    }
+
+    impl Plugin for NeedsConfigured {
+        fn build(&self, app: &mut AppBuilder) {
+            record_build(app, "NeedsConfigured");
+        }
+        fn dependencies(&self) -> Vec<PluginDependency> {
+            vec![PluginDependency::existing::<Configured>(
+                "reads configuration",
+            )]
+        }
+    }
+
+    impl Plugin for MaybeConfigured {
+        fn build(&self, app: &mut AppBuilder) {
+            record_build(app, "MaybeConfigured");
+        }
+        fn dependencies(&self) -> Vec<PluginDependency> {
+            vec![PluginDependency::optional::<Configured>(
+                "reads configuration if any",
+            )]
+        }
+    }
+
+    #[derive(Component)]
+    struct Provided(&'static str);
+
+    impl Plugin for AddsBase {
+        fn build(&self, app: &mut AppBuilder) {
+            record_build(app, "AddsBase");
+            app.add_plugin(Base::default()).add_plugin(NeedsBase);
+            let provided = app
+                .app
+                .world
+                .run(|provided: UniqueView<Provided>| provided.0)
+                .unwrap();
+            record_build(app, provided);
+        }
+    }
+
+    impl Plugin for AddsNeedsConfigured {
+        fn build(&self, app: &mut AppBuilder) {
+            record_build(app, "AddsNeedsConfigured");
+            app.add_plugin(NeedsConfigured);
+        }
+    }
+
+    impl Plugin for CycleA {
+        fn build(&self, _: &mut AppBuilder) {}
+        fn dependencies(&self) -> Vec<PluginDependency> {
+            vec![PluginDependency::existing::<CycleB>("a needs b")]
+        }
+    }
+
+    impl Plugin for CycleB {
+        fn build(&self, _: &mut AppBuilder) {}
+        fn dependencies(&self) -> Vec<PluginDependency> {
+            vec![PluginDependency::existing::<CycleA>("b needs a")]
+        }
+    }
+
+    fn setup_app() -> App {
+        let app = App::new();
+        app.world.add_unique(BuildOrder::default()).unwrap();
+        app
+    }
+
+    #[test]
+    fn default_dependencies_are_added_and_built_first() {
+        let app = setup_app();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(NeedsBase);
+        builder.try_finish().expect("dependencies resolved");
+
+        assert_eq!(build_order(&app), vec!["Base", "NeedsBase"]);
+    }
+
+    #[test]
+    fn default_dependencies_can_be_added_after_dependents() {
+        let app = setup_app();
+        let mut builder = AppBuilder::new(&app);
+        builder
+            .add_plugin(NeedsBase)
+            .add_plugin(Base("provided by configured Base"));
+        builder.try_finish().expect("no default Base added");
+
+        assert_eq!(build_order(&app), vec!["Base", "NeedsBase"]);
+        assert_eq!(
+            app.run(|provided: UniqueView<Provided>| provided.0),
+            "provided by configured Base"
+        );
+    }
+
+    #[test]
+    fn dependencies_are_built_first_regardless_of_add_order() {
+        let app = setup_app();
+        let mut builder = AppBuilder::new(&app);
+        builder
+            .add_plugin(NeedsConfigured)
+            .add_plugin(MaybeConfigured)
+            .add_plugin(Configured);
+        builder.try_finish().expect("dependencies resolved");
+
+        assert_eq!(
+            build_order(&app),
+            vec!["Configured", "NeedsConfigured", "MaybeConfigured"]
+        );
+    }
+
+    #[test]
+    fn nested_plugins_are_built_where_added() {
+        let app = setup_app();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(AddsBase).add_plugin(MaybeConfigured);
+        builder.try_finish().expect("dependencies resolved");
+
+        assert_eq!(
+            build_order(&app),
+            vec![
+                "AddsBase",
+                "Base",
+                "NeedsBase",
+                "provided by Base",
+                "MaybeConfigured"
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_plugins_wait_for_unbuilt_dependencies() {
+        let app = setup_app();
+        let mut builder = AppBuilder::new(&app);
+        builder
+            .add_plugin(AddsNeedsConfigured)
+            .add_plugin(Configured);
+        builder.try_finish().expect("dependencies resolved");
+
+        assert_eq!(
+            build_order(&app),
+            vec!["AddsNeedsConfigured", "Configured", "NeedsConfigured"]
+        );
+    }
+
+    #[test]
+    fn optional_dependencies_may_be_missing() {
+        let app = setup_app();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(MaybeConfigured);
+        builder
+            .try_finish()
+            .expect("optional dependency is not required");
+
+        assert_eq!(build_order(&app), vec!["MaybeConfigured"]);
+    }
+
+    #[test]
+    fn missing_dependencies_are_reported() {
+        let app = setup_app();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(NeedsConfigured);
+        let errors = builder
+            .try_finish()
+            .expect_err("expected missing dependency");
+
+        assert!(
+            matches!(
+                errors.as_slice(),
+                [AppBuildError::MissingPluginDependency {
+                    reason: "reads configuration",
+                    ..
+                }]
+            ),
+            "Expected 1 MissingPluginDependency error, but found: {:#?}",
+            errors
+        );
+        assert_eq!(build_order(&app), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn dependency_cycles_are_reported() {
+        let app = setup_app();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(CycleA).add_plugin(CycleB);
+        let errors = builder.try_finish().expect_err("expected cycle");
+
+        assert_eq!(
+            errors.len(),
+            1,
+            "Expected 1 error, but found: {:#?}",
+            errors
+        );
+        if let AppBuildError::PluginDependencyCycle { chain } = &errors[0] {
+            let chain = chain.to_string();
+            assert!(
+                chain.contains("CycleA") && chain.contains("CycleB"),
+                "{}",
+                chain
+            );
+        } else {
+            panic!(
+                "Expected error to be PluginDependencyCycle, but found {:#?}",
+                errors[0]
+            );
+        }
+    }
+}
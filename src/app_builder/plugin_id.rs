@@ -9,10 +9,14 @@ impl PluginId {
             .any(|(existing_type_id, _)| type_id.eq(existing_type_id))
     }
     pub(crate) fn push<T: 'static>(&mut self) {
-        self.0.push((TypeId::of::<T>(), type_name::<T>()));
+        self.push_named(TypeId::of::<T>(), type_name::<T>());
     }
-    pub(crate) fn pop(&mut self) {
-        self.0.pop();
+    pub(crate) fn push_named(&mut self, type_id: TypeId, type_name: &'static str) {
+        self.0.push((type_id, type_name));
+    }
+    /// The most nested plugin
+    pub(crate) fn last(&self) -> Option<(TypeId, &'static str)> {
+        self.0.last().copied()
     }
 }
 
@@ -0,0 +1,232 @@
+//! Stages group the systems of an [AppWorkload](crate::AppWorkload) so they run in a fixed order.
+
+/// Stages systems can be added to, each becomes its own shipyard workload run in order by [AppWorkload::run](crate::AppWorkload::run).
+///
+/// The default order is [Stage::PreUpdate], [Stage::Update], [Stage::PostUpdate], then [Stage::Reset],
+/// with [Stage::Startup] run once before all others the first time the workload is run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// Runs only the first time the workload is run
+    Startup,
+    PreUpdate,
+    /// Where [AppBuilder::add_system](crate::AppBuilder::add_system) systems go
+    Update,
+    PostUpdate,
+    /// Where [AppBuilder::add_reset_system](crate::AppBuilder::add_reset_system) systems go, after all other stages
+    Reset,
+    /// A stage added with [AppBuilder::add_stage_before](crate::AppBuilder::add_stage_before) or [AppBuilder::add_stage_after](crate::AppBuilder::add_stage_after)
+    Named(&'static str),
+}
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Startup => "startup",
+            Stage::PreUpdate => "pre_update",
+            Stage::Update => "update",
+            Stage::PostUpdate => "post_update",
+            Stage::Reset => "reset",
+            Stage::Named(name) => name,
+        }
+    }
+
+    /// Stages run every update in the default order
+    pub(crate) fn default_order() -> Vec<Stage> {
+        vec![
+            Stage::PreUpdate,
+            Stage::Update,
+            Stage::PostUpdate,
+            Stage::Reset,
+        ]
+    }
+}
+
+impl Default for Stage {
+    fn default() -> Self {
+        Stage::Update
+    }
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{App, AppBuildError, AppBuilder, Plugin, Stage, SystemConfig};
+    use shipyard::{Component, UniqueView, UniqueViewMut};
+
+    #[derive(Component, Default)]
+    struct RunOrder(Vec<&'static str>);
+
+    macro_rules! record_system {
+        ($name: ident) => {
+            fn $name(mut order: UniqueViewMut<RunOrder>) {
+                order.0.push(stringify!($name));
+            }
+        };
+    }
+
+    record_system!(startup);
+    record_system!(pre_update);
+    record_system!(update);
+    record_system!(layout);
+    record_system!(post_update);
+    record_system!(reset);
+
+    struct StagedPlugin;
+
+    impl Plugin for StagedPlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            // added out of order to show the stages decide the order
+            app.add_stage_after("layout", Stage::Update)
+                .add_reset_system(reset, "clear for next update")
+                .add_system_to_stage(Stage::PostUpdate, post_update)
+                .add_system_to_stage(Stage::Named("layout"), layout)
+                .add_system(update)
+                .add_system_with(pre_update, SystemConfig::new().in_stage(Stage::PreUpdate))
+                .add_system_to_stage(Stage::Startup, startup);
+        }
+    }
+
+    struct UnknownStagePlugin;
+
+    impl Plugin for UnknownStagePlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            app.add_system_to_stage(Stage::Named("not added"), update);
+        }
+    }
+
+    struct AfterResetPlugin;
+
+    impl Plugin for AfterResetPlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            app.add_stage_after("cleanup", Stage::Reset)
+                .add_system_to_stage(Stage::Named("cleanup"), reset);
+        }
+    }
+
+    fn take_run_order(app: &App) -> Vec<&'static str> {
+        let order = app.run(|order: UniqueView<RunOrder>| order.0.clone());
+        app.run(|mut order: UniqueViewMut<RunOrder>| order.0.clear());
+        order
+    }
+
+    #[test]
+    fn stages_run_in_order_with_startup_once() {
+        let mut app = App::new();
+        app.world.add_unique(RunOrder::default()).unwrap();
+        let workload = app.add_plugin_workload(StagedPlugin);
+
+        workload.run(&app);
+        assert_eq!(
+            take_run_order(&app),
+            vec![
+                "startup",
+                "pre_update",
+                "update",
+                "layout",
+                "post_update",
+                "reset"
+            ]
+        );
+
+        workload.run(&app);
+        assert_eq!(
+            take_run_order(&app),
+            vec!["pre_update", "update", "layout", "post_update", "reset"]
+        );
+    }
+
+    #[test]
+    fn workload_info_covers_every_stage() {
+        let mut app = App::new();
+        let (_, info) = app.add_plugin_workload_with_info(StagedPlugin);
+
+        let batches = format!("{:?}", info.batch_info);
+        for system in ["startup", "pre_update", "layout", "post_update", "reset"].iter() {
+            assert!(
+                batches.contains(&format!("tests::{}", system)),
+                "{} missing from {}",
+                system,
+                batches
+            );
+        }
+    }
+
+    #[test]
+    fn default_workload_runs_stages_on_update() {
+        let app = App::new();
+        app.world.add_unique(RunOrder::default()).unwrap();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(StagedPlugin);
+        builder.finish();
+
+        app.update();
+        app.update();
+        assert_eq!(
+            take_run_order(&app),
+            vec![
+                "startup",
+                "pre_update",
+                "update",
+                "layout",
+                "post_update",
+                "reset",
+                "pre_update",
+                "update",
+                "layout",
+                "post_update",
+                "reset"
+            ]
+        );
+    }
+
+    #[test]
+    fn stages_after_reset_are_rejected() {
+        let mut app = App::new();
+        let errors = app
+            .try_add_plugin_workload(AfterResetPlugin)
+            .expect_err("expected stage after reset");
+
+        assert!(
+            matches!(
+                errors.as_slice(),
+                [
+                    AppBuildError::StageAfterReset {
+                        stage: "cleanup",
+                        ..
+                    },
+                    AppBuildError::UnknownStage {
+                        stage: "cleanup",
+                        ..
+                    }
+                ]
+            ),
+            "Expected StageAfterReset and UnknownStage errors, but found: {:#?}",
+            errors
+        );
+    }
+
+    #[test]
+    fn systems_in_unknown_stages_are_reported() {
+        let mut app = App::new();
+        let errors = app
+            .try_add_plugin_workload(UnknownStagePlugin)
+            .expect_err("expected unknown stage");
+
+        assert!(
+            matches!(
+                errors.as_slice(),
+                [AppBuildError::UnknownStage {
+                    stage: "not added",
+                    ..
+                }]
+            ),
+            "Expected 1 UnknownStage error, but found: {:#?}",
+            errors
+        );
+    }
+}
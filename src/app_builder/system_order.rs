@@ -0,0 +1,332 @@
+//! Orders the systems added through [AppBuilder](crate::AppBuilder) by their labels.
+use shipyard::WorkloadSystem;
+use tracing::*;
+
+use super::{AppBuildError, PluginId, Stage};
+use crate::RunCondition;
+
+/// Stage, run conditions, labels and ordering constraints for a system added with [AppBuilder::add_system_with](crate::AppBuilder::add_system_with).
+///
+/// Ordering constraints only apply between systems in the same [Stage].
+///
+/// ```
+/// use shipyard_app::SystemConfig;
+///
+/// let reordering = SystemConfig::labeled("tree::reordering").before("tree::indexing");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SystemConfig {
+    pub(crate) stage: Stage,
+    pub(crate) conditions: Vec<RunCondition>,
+    pub(crate) labels: Vec<&'static str>,
+    pub(crate) before: Vec<&'static str>,
+    pub(crate) after: Vec<&'static str>,
+}
+
+impl SystemConfig {
+    pub fn new() -> Self {
+        SystemConfig::default()
+    }
+
+    pub fn labeled(label: &'static str) -> Self {
+        SystemConfig::new().label(label)
+    }
+
+    /// Run during this stage instead of [Stage::Update].
+    pub fn in_stage(mut self, stage: Stage) -> Self {
+        self.stage = stage;
+        self
+    }
+
+    /// Only run when `condition` holds, adding more conditions requires all of them to hold.
+    pub fn run_if(mut self, condition: RunCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Other systems can be ordered relative to this system (and all others sharing the label).
+    pub fn label(mut self, label: &'static str) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    /// Run before every system with this label.
+    pub fn before(mut self, label: &'static str) -> Self {
+        self.before.push(label);
+        self
+    }
+
+    /// Run after every system with this label.
+    pub fn after(mut self, label: &'static str) -> Self {
+        self.after.push(label);
+        self
+    }
+}
+
+/// A system waiting to be added to a workload, with the plugin which added it.
+pub(crate) struct AppSystem {
+    pub(crate) system: WorkloadSystem,
+    pub(crate) name: &'static str,
+    pub(crate) plugin: PluginId,
+    pub(crate) config: SystemConfig,
+}
+
+impl AppSystem {
+    fn describe(&self) -> (&'static str, PluginId) {
+        (self.name, self.plugin.clone())
+    }
+}
+
+/// Sort systems so every `before` / `after` constraint is met, otherwise keeping the order systems were added in.
+///
+/// Systems are split into groups, a new group starting whenever a system must run after a system of the current group.
+/// Shipyard may run systems of one workload in parallel when their borrows do not conflict, so each group needs its own workload.
+///
+/// Constraints on labels which no system has are ignored, since the labeled system may belong to a plugin which was not added.
+pub(crate) fn order_systems(systems: Vec<AppSystem>) -> Result<Vec<Vec<AppSystem>>, AppBuildError> {
+    // runs_after[i] lists the systems which must run before system i
+    let mut runs_after = vec![Vec::<usize>::new(); systems.len()];
+    for (idx, system) in systems.iter().enumerate() {
+        for label in system.config.after.iter() {
+            let labeled = with_label(&systems, label, idx);
+            if labeled.is_empty() {
+                trace!(system = system.name, plugin = ?system.plugin, label = *label, "no systems to run after");
+            }
+            runs_after[idx].extend(labeled);
+        }
+        for label in system.config.before.iter() {
+            let labeled = with_label(&systems, label, idx);
+            if labeled.is_empty() {
+                trace!(system = system.name, plugin = ?system.plugin, label = *label, "no systems to run before");
+            }
+            for other in labeled {
+                runs_after[other].push(idx);
+            }
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(systems.len());
+    let mut placed = vec![false; systems.len()];
+    // always place the earliest added system which is ready, to keep the added order where possible
+    while let Some(next) = (0..systems.len())
+        .find(|&idx| !placed[idx] && runs_after[idx].iter().all(|&before| placed[before]))
+    {
+        placed[next] = true;
+        ordered.push(next);
+    }
+
+    if ordered.len() < systems.len() {
+        let start = (0..systems.len())
+            .find(|&idx| !placed[idx])
+            .expect("a system was not placed");
+        let cycle = find_order_cycle(&runs_after, &placed, start);
+        return Err(AppBuildError::SystemOrderCycle {
+            systems: cycle
+                .iter()
+                .chain(cycle.first())
+                .map(|&idx| systems[idx].describe())
+                .collect(),
+        });
+    }
+
+    let mut group_of = vec![0; systems.len()];
+    let mut groups = Vec::<Vec<usize>>::new();
+    for idx in ordered {
+        let current = groups.len().checked_sub(1);
+        let follows_current = runs_after[idx]
+            .iter()
+            .any(|&before| Some(group_of[before]) == current);
+        if current.is_none() || follows_current {
+            groups.push(Vec::new());
+        }
+        group_of[idx] = groups.len() - 1;
+        groups
+            .last_mut()
+            .expect("a group was just pushed")
+            .push(idx);
+    }
+
+    let mut systems = systems.into_iter().map(Some).collect::<Vec<_>>();
+    Ok(groups
+        .into_iter()
+        .map(|group| {
+            group
+                .into_iter()
+                .map(|idx| systems[idx].take().expect("each system is ordered once"))
+                .collect()
+        })
+        .collect())
+}
+
+fn with_label(systems: &[AppSystem], label: &str, except: usize) -> Vec<usize> {
+    systems
+        .iter()
+        .enumerate()
+        .filter(|(idx, system)| {
+            *idx != except && system.config.labels.iter().any(|other| *other == label)
+        })
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Every unplaced system is waiting on another unplaced system, so following them from `start` must loop.
+fn find_order_cycle(runs_after: &[Vec<usize>], placed: &[bool], start: usize) -> Vec<usize> {
+    let mut path = vec![start];
+    let mut current = start;
+    loop {
+        let waiting_on = runs_after[current]
+            .iter()
+            .copied()
+            .find(|&before| !placed[before])
+            .expect("unplaced system is waiting on another unplaced system");
+        if let Some(cycle_start) = path.iter().position(|&idx| idx == waiting_on) {
+            // path is followed backwards (from a system to what it waits on), so flip it to read in run order
+            let mut cycle = path[cycle_start..].to_vec();
+            cycle.reverse();
+            return cycle;
+        }
+        path.push(waiting_on);
+        current = waiting_on;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{App, AppBuildError, AppBuilder, Plugin, SystemConfig};
+    use shipyard::{Component, UniqueView, UniqueViewMut};
+
+    #[derive(Component, Default)]
+    struct RunOrder(Vec<&'static str>);
+
+    /// Shared by uniques in separate storages, so systems logging to it do not conflict
+    type SharedLog = std::sync::Arc<std::sync::Mutex<Vec<&'static str>>>;
+
+    #[derive(Component)]
+    struct SlowLog(SharedLog);
+
+    #[derive(Component)]
+    struct FastLog(SharedLog);
+
+    fn slow(log: UniqueView<SlowLog>) {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        log.0.lock().unwrap().push("slow");
+    }
+
+    fn fast(log: UniqueView<FastLog>) {
+        log.0.lock().unwrap().push("fast");
+    }
+
+    fn indexing(mut order: UniqueViewMut<RunOrder>) {
+        order.0.push("indexing");
+    }
+
+    fn reordering(mut order: UniqueViewMut<RunOrder>) {
+        order.0.push("reordering");
+    }
+
+    fn rendering(mut order: UniqueViewMut<RunOrder>) {
+        order.0.push("rendering");
+    }
+
+    struct IndexingPlugin;
+    struct ReorderingPlugin;
+    struct ContradictingPlugin;
+
+    impl Plugin for IndexingPlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            app.add_system(rendering)
+                .add_system_with(indexing, SystemConfig::labeled("indexing"));
+        }
+    }
+
+    impl Plugin for ReorderingPlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            app.add_system_with(
+                reordering,
+                SystemConfig::labeled("reordering")
+                    .before("indexing")
+                    .before("not added"),
+            );
+        }
+    }
+
+    impl Plugin for ContradictingPlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            app.add_system_with(
+                rendering,
+                SystemConfig::new().after("indexing").before("reordering"),
+            );
+        }
+    }
+
+    fn setup_app() -> App {
+        let app = App::new();
+        app.world.add_unique(RunOrder::default()).unwrap();
+        app
+    }
+
+    #[test]
+    fn systems_run_in_labeled_order() {
+        let app = setup_app();
+        let mut builder = AppBuilder::new(&app);
+        builder
+            .add_plugin(IndexingPlugin)
+            .add_plugin(ReorderingPlugin);
+        builder.try_finish().expect("ordering is satisfiable");
+
+        app.update();
+
+        assert_eq!(
+            app.run(|order: UniqueView<RunOrder>| order.0.clone()),
+            vec!["rendering", "reordering", "indexing"]
+        );
+    }
+
+    #[test]
+    fn order_holds_between_disjoint_systems() {
+        let app = App::new();
+        let log = SharedLog::default();
+        app.world.add_unique(SlowLog(log.clone())).unwrap();
+        app.world.add_unique(FastLog(log.clone())).unwrap();
+        let mut builder = AppBuilder::new(&app);
+        builder
+            .add_system_with(fast, SystemConfig::new().after("slow"))
+            .add_system_with(slow, SystemConfig::labeled("slow"));
+        builder.try_finish().expect("ordering is satisfiable");
+
+        app.update();
+
+        assert_eq!(*log.lock().unwrap(), vec!["slow", "fast"]);
+    }
+
+    #[test]
+    fn contradicting_order_names_plugins() {
+        let app = setup_app();
+        let mut builder = AppBuilder::new(&app);
+        builder
+            .add_plugin(IndexingPlugin)
+            .add_plugin(ReorderingPlugin)
+            .add_plugin(ContradictingPlugin);
+        let errors = builder.try_finish().expect_err("expected ordering cycle");
+
+        assert_eq!(
+            errors.len(),
+            1,
+            "Expected 1 error, but found: {:#?}",
+            errors
+        );
+        let message = errors[0].to_string();
+        if let AppBuildError::SystemOrderCycle { systems } = &errors[0] {
+            // rendering → reordering → indexing → rendering
+            assert_eq!(systems.len(), 4, "{}", message);
+        } else {
+            panic!(
+                "Expected error to be SystemOrderCycle, but found {}",
+                message
+            );
+        }
+        assert!(message.contains("IndexingPlugin"), "{}", message);
+        assert!(message.contains("ReorderingPlugin"), "{}", message);
+        assert!(message.contains("ContradictingPlugin"), "{}", message);
+    }
+}
@@ -3,6 +3,7 @@ mod app;
 mod app_add_cycle;
 mod app_builder;
 mod plugin;
+mod run_condition;
 mod tracked_unique;
 mod type_names;
 mod update_one_to_one;
@@ -12,6 +13,7 @@ pub use add_distinct::*;
 pub use app::*;
 pub use app_builder::*;
 pub use plugin::*;
+pub use run_condition::*;
 pub use shipyard::*;
 pub use tracked_unique::*;
 pub use update_one_to_one::*;
@@ -23,15 +25,14 @@ pub mod prelude {
     pub use crate::{
         add_distinct::AddDistinct,
         app::App,
-        app_builder::{AppBuilder, AppWorkload},
-        plugin::Plugin,
+        app_builder::{AppBuilder, AppWorkload, Stage, SystemConfig},
+        plugin::{Plugin, PluginDependency},
+        run_condition::RunCondition,
         update_one_to_one::UpdateOneToOne,
         update_two_to_one::UpdateTwoToOne,
     };
     pub use shipyard::*;
 }
 
-#[cfg(test)]
-mod test {
-    mod tree;
-}
+#[cfg(feature = "tree")]
+pub mod tree;
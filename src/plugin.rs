@@ -1,4 +1,4 @@
-use std::any::{type_name, Any};
+use std::any::{type_name, Any, TypeId};
 
 use crate::AppBuilder;
 
@@ -14,4 +14,62 @@ pub trait Plugin: Any + Send + Sync {
     fn can_add_multiple_times(&self) -> bool {
         false
     }
+    /// Plugins which must be built before this plugin, regardless of the order plugins are added to the [AppBuilder].
+    fn dependencies(&self) -> Vec<PluginDependency> {
+        Vec::new()
+    }
+}
+
+/// Declares that a plugin must be built after another plugin, see [Plugin::dependencies].
+#[derive(Clone)]
+pub struct PluginDependency {
+    pub(crate) type_id: TypeId,
+    pub(crate) name: &'static str,
+    pub(crate) reason: &'static str,
+    pub(crate) optional: bool,
+    pub(crate) default: Option<fn() -> Box<dyn Plugin>>,
+}
+
+impl PluginDependency {
+    /// Depend on `P`, adding `P::default()` if no other plugin adds it.
+    pub fn new<P: Plugin + Default>(reason: &'static str) -> Self {
+        PluginDependency {
+            default: Some(default_plugin::<P> as fn() -> Box<dyn Plugin>),
+            ..PluginDependency::existing::<P>(reason)
+        }
+    }
+
+    /// Depend on `P`, which must be added by another plugin.
+    pub fn existing<P: Plugin>(reason: &'static str) -> Self {
+        PluginDependency {
+            type_id: TypeId::of::<P>(),
+            name: type_name::<P>(),
+            reason,
+            optional: false,
+            default: None,
+        }
+    }
+
+    /// Build after `P` only if another plugin adds it.
+    pub fn optional<P: Plugin>(reason: &'static str) -> Self {
+        PluginDependency {
+            optional: true,
+            ..PluginDependency::existing::<P>(reason)
+        }
+    }
+}
+
+impl std::fmt::Debug for PluginDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginDependency")
+            .field("plugin", &self.name)
+            .field("reason", &self.reason)
+            .field("optional", &self.optional)
+            .field("default", &self.default.is_some())
+            .finish()
+    }
+}
+
+fn default_plugin<P: Plugin + Default>() -> Box<dyn Plugin> {
+    Box::new(P::default())
 }
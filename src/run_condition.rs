@@ -0,0 +1,241 @@
+//! Conditions deciding whether systems run on each update, see [SystemConfig::run_if](crate::SystemConfig::run_if) and [AppBuilder::add_plugin_if](crate::AppBuilder::add_plugin_if).
+use std::{any::type_name, sync::Arc};
+
+use shipyard::{track, Component, UniqueView, View, World};
+
+/// Checked against the [World] before running the systems it applies to, which are skipped when it returns false.
+#[derive(Clone)]
+pub struct RunCondition {
+    name: &'static str,
+    check: Arc<dyn Fn(&World) -> bool + Send + Sync>,
+}
+
+impl RunCondition {
+    /// Create a condition from any check of the [World], the name is used for tracing.
+    pub fn new<F>(name: &'static str, check: F) -> Self
+    where
+        F: Fn(&World) -> bool + Send + Sync + 'static,
+    {
+        RunCondition {
+            name,
+            check: Arc::new(check),
+        }
+    }
+
+    /// Run if the unique `T` was inserted or modified since it was last reset.
+    ///
+    /// Uniques added with [TrackedUniquePlugin](crate::TrackedUniquePlugin) or declared with [AppBuilder::tracks](crate::AppBuilder::tracks) are reset at the end of every update.
+    pub fn tracked_unique_modified<T>() -> Self
+    where
+        T: Component<Tracking = track::All> + Send + Sync,
+    {
+        RunCondition::new(type_name::<T>(), |world| {
+            world
+                .borrow::<UniqueView<T>>()
+                .map_or(false, |unique| unique.is_inserted_or_modified())
+        })
+    }
+
+    /// Run if any component `T` was inserted or modified since the storage was last cleared.
+    ///
+    /// [AppBuilder::update_pack](crate::AppBuilder::update_pack) does not clear the storage, clear it at the end of every update
+    /// by adding [reset_tracked_storage](crate::reset_tracked_storage) with [AppBuilder::add_reset_system](crate::AppBuilder::add_reset_system).
+    pub fn storage_changed<T>() -> Self
+    where
+        T: Component<Tracking = track::All> + Send + Sync,
+    {
+        RunCondition::new(type_name::<T>(), |world| {
+            world.borrow::<View<T>>().map_or(false, |view| {
+                view.inserted_or_modified().iter().next().is_some()
+            })
+        })
+    }
+
+    /// Run if the unique `T` exists and `predicate` returns true for it, e.g. a flag being set.
+    pub fn unique_matches<T>(predicate: fn(&T) -> bool) -> Self
+    where
+        T: Component + Send + Sync,
+    {
+        RunCondition::new(type_name::<T>(), move |world| {
+            world
+                .borrow::<UniqueView<T>>()
+                .map_or(false, |unique| predicate(&unique))
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub(crate) fn check(&self, world: &World) -> bool {
+        (self.check)(world)
+    }
+
+    /// Whether both lists hold the very same conditions, so systems using them can share a workload.
+    pub(crate) fn same_conditions(a: &[RunCondition], b: &[RunCondition]) -> bool {
+        a.len() == b.len()
+            && a.iter()
+                .zip(b.iter())
+                .all(|(a, b)| Arc::ptr_eq(&a.check, &b.check))
+    }
+}
+
+impl std::fmt::Debug for RunCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("RunCondition").field(&self.name).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{reset_tracked_storage, App, AppBuilder, Plugin, RunCondition, SystemConfig};
+    use shipyard::{Component, EntitiesViewMut, UniqueView, UniqueViewMut, View, ViewMut};
+
+    #[derive(Component, Default)]
+    struct RunCount(Vec<&'static str>);
+
+    #[derive(Clone, Component, Default)]
+    #[track(All)]
+    struct Selection(u32);
+
+    #[derive(Component, Default)]
+    struct Paused(bool);
+
+    fn on_selection(mut count: UniqueViewMut<RunCount>) {
+        count.0.push("on_selection");
+    }
+
+    fn unless_paused(mut count: UniqueViewMut<RunCount>) {
+        count.0.push("unless_paused");
+    }
+
+    fn always(mut count: UniqueViewMut<RunCount>) {
+        count.0.push("always");
+    }
+
+    struct SelectionPlugin;
+
+    impl Plugin for SelectionPlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            app.add_tracked_value(Selection(0))
+                .tracks::<Selection>("run on_selection only when changed")
+                .add_system(always)
+                .add_system_with(
+                    on_selection,
+                    SystemConfig::new()
+                        .run_if(RunCondition::tracked_unique_modified::<Selection>()),
+                );
+        }
+    }
+
+    #[derive(Component)]
+    #[track(All)]
+    struct Score(u32);
+
+    fn on_score(mut count: UniqueViewMut<RunCount>) {
+        count.0.push("on_score");
+    }
+
+    struct ScorePlugin;
+
+    impl Plugin for ScorePlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            app.update_pack::<Score>("run on_score only when changed")
+                .add_system_with(
+                    on_score,
+                    SystemConfig::new().run_if(RunCondition::storage_changed::<Score>()),
+                )
+                .add_reset_system(reset_tracked_storage::<Score>, "on_score sees new changes");
+        }
+    }
+
+    struct PausablePlugin;
+
+    impl Plugin for PausablePlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            app.add_system(unless_paused);
+        }
+    }
+
+    fn take_run_count(app: &App) -> Vec<&'static str> {
+        app.run(|mut count: UniqueViewMut<RunCount>| std::mem::take(&mut count.0))
+    }
+
+    #[test]
+    fn systems_run_only_when_tracked_unique_modified() {
+        let app = App::new();
+        app.world.add_unique(RunCount::default()).unwrap();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(SelectionPlugin);
+        builder.finish();
+
+        // inserting the unique counts as a change
+        app.update();
+        assert_eq!(take_run_count(&app), vec!["always", "on_selection"]);
+
+        app.update();
+        assert_eq!(take_run_count(&app), vec!["always"]);
+
+        app.run(|mut selection: UniqueViewMut<Selection>| selection.0 = 1);
+        app.update();
+        assert_eq!(take_run_count(&app), vec!["always", "on_selection"]);
+        assert_eq!(app.run(|selection: UniqueView<Selection>| selection.0), 1);
+    }
+
+    #[test]
+    fn storage_changed_until_reset() {
+        let app = App::new();
+        app.world.add_unique(RunCount::default()).unwrap();
+        app.world.add_unique(Paused(false)).unwrap();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin_if(
+            ScorePlugin,
+            RunCondition::unique_matches(|paused: &Paused| !paused.0),
+        );
+        builder.finish();
+
+        app.run(
+            |mut entities: EntitiesViewMut, mut vm_score: ViewMut<Score>| {
+                entities.add_entity(&mut vm_score, Score(0));
+            },
+        );
+        app.update();
+        assert_eq!(take_run_count(&app), vec!["on_score"]);
+
+        app.update();
+        assert_eq!(take_run_count(&app), Vec::<&str>::new());
+
+        // the reset ignores the plugin's condition
+        app.run(|mut paused: UniqueViewMut<Paused>| paused.0 = true);
+        app.run(
+            |mut entities: EntitiesViewMut, mut vm_score: ViewMut<Score>| {
+                entities.add_entity(&mut vm_score, Score(1));
+            },
+        );
+        app.update();
+        assert_eq!(take_run_count(&app), Vec::<&str>::new());
+        app.run(|v_score: View<Score>| {
+            assert_eq!(v_score.inserted_or_modified().iter().count(), 0);
+        });
+    }
+
+    #[test]
+    fn plugin_conditions_apply_to_all_their_systems() {
+        let app = App::new();
+        app.world.add_unique(RunCount::default()).unwrap();
+        app.world.add_unique(Paused(false)).unwrap();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_system(always).add_plugin_if(
+            PausablePlugin,
+            RunCondition::unique_matches(|paused: &Paused| !paused.0),
+        );
+        builder.finish();
+
+        app.update();
+        assert_eq!(take_run_count(&app), vec!["always", "unless_paused"]);
+
+        app.run(|mut paused: UniqueViewMut<Paused>| paused.0 = true);
+        app.update();
+        assert_eq!(take_run_count(&app), vec!["always"]);
+    }
+}
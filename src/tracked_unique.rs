@@ -4,8 +4,11 @@ use tracing::trace_span;
 use crate::prelude::*;
 
 use core::any::type_name;
+use core::ops::{Deref, DerefMut};
 
-/// Add [TrackedUnique] of `T` and reset tracking at the end of every update.
+/// Add a tracked unique `T`, accessible through [Tracked] and [TrackedMut], and reset tracking at the end of every update.
+///
+/// The value at the last reset is kept as [Previous]`<T>`, see [TrackedChange].
 #[derive(Default)]
 pub struct TrackedUniquePlugin<T: Clone + Send + Sync + Component<Tracking = track::All>>(T);
 
@@ -17,7 +20,237 @@ impl<T: Clone + Send + Sync + Component<Tracking = track::All>> TrackedUniquePlu
 
 impl<T: Clone + Send + Sync + Component<Tracking = track::All>> Plugin for TrackedUniquePlugin<T> {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_unique(self.0.clone());
+        app.add_tracked_value(self.0.clone())
+            .add_unique(Previous(self.0.clone()))
+            .tracks_previous::<T>("TrackedUniquePlugin resets tracking after every update");
+    }
+}
+
+/// Read a tracked unique `T` and whether it changed since tracking was last reset.
+///
+/// ```
+/// use shipyard_app::prelude::*;
+/// use shipyard_app::{Tracked, TrackedMut, TrackedUniquePlugin};
+///
+/// #[derive(Clone, Component, Default)]
+/// #[track(All)]
+/// struct Zoom(f32);
+///
+/// let app = App::new();
+/// let mut builder = AppBuilder::new(&app);
+/// builder.add_plugin(TrackedUniquePlugin::new(Zoom(1.0)));
+/// builder.finish();
+///
+/// // the reset at the end of the update clears the insertion
+/// app.update();
+/// assert!(!app.run(|zoom: Tracked<Zoom>| zoom.is_inserted_or_modified()));
+///
+/// app.run(|mut zoom: TrackedMut<Zoom>| zoom.0 = 2.0);
+/// assert!(app.run(|zoom: Tracked<Zoom>| zoom.is_modified()));
+/// ```
+pub struct Tracked<'a, T: Component<Tracking = track::All>>(UniqueView<'a, T>);
+
+/// Modify a tracked unique `T`, flagging it as modified whenever it is mutably dereferenced.
+pub struct TrackedMut<'a, T: Component<Tracking = track::All>>(UniqueViewMut<'a, T>);
+
+impl<T: Component<Tracking = track::All>> Tracked<'_, T> {
+    pub fn is_inserted(&self) -> bool {
+        self.0.is_inserted()
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.0.is_modified()
+    }
+
+    pub fn is_inserted_or_modified(&self) -> bool {
+        self.0.is_inserted_or_modified()
+    }
+}
+
+impl<T: Component<Tracking = track::All>> TrackedMut<'_, T> {
+    pub fn is_inserted(&self) -> bool {
+        self.0.is_inserted()
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.0.is_modified()
+    }
+
+    pub fn is_inserted_or_modified(&self) -> bool {
+        self.0.is_inserted_or_modified()
+    }
+}
+
+impl<T: Component<Tracking = track::All>> Deref for Tracked<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &*self.0
+    }
+}
+
+impl<T: Component<Tracking = track::All>> Deref for TrackedMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &*self.0
+    }
+}
+
+impl<T: Component<Tracking = track::All>> DerefMut for TrackedMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut *self.0
+    }
+}
+
+pub struct TrackedBorrower<T>(T);
+
+pub struct TrackedMutBorrower<T>(T);
+
+impl<T> IntoBorrow for Tracked<'_, T>
+where
+    T: Send + Sync + Component<Tracking = track::All>,
+{
+    type Borrow = TrackedBorrower<T>;
+}
+
+impl<T> IntoBorrow for TrackedMut<'_, T>
+where
+    T: Send + Sync + Component<Tracking = track::All>,
+{
+    type Borrow = TrackedMutBorrower<T>;
+}
+
+impl<'a, T> Borrow<'a> for TrackedBorrower<T>
+where
+    T: Send + Sync + Component<Tracking = track::All>,
+{
+    type View = Tracked<'a, T>;
+
+    fn borrow(
+        world: &'a World,
+        last_run: Option<u32>,
+        current: u32,
+    ) -> Result<Self::View, error::GetStorage> {
+        Ok(Tracked(<UniqueView<T> as IntoBorrow>::Borrow::borrow(
+            world, last_run, current,
+        )?))
+    }
+}
+
+impl<'a, T> Borrow<'a> for TrackedMutBorrower<T>
+where
+    T: Send + Sync + Component<Tracking = track::All>,
+{
+    type View = TrackedMut<'a, T>;
+
+    fn borrow(
+        world: &'a World,
+        last_run: Option<u32>,
+        current: u32,
+    ) -> Result<Self::View, error::GetStorage> {
+        Ok(TrackedMut(
+            <UniqueViewMut<T> as IntoBorrow>::Borrow::borrow(world, last_run, current)?,
+        ))
+    }
+}
+
+unsafe impl<'a, T: Component<Tracking = track::All> + Send + Sync> BorrowInfo for Tracked<'a, T> {
+    fn borrow_info(mut info: &mut Vec<info::TypeInfo>) {
+        UniqueView::<'a, T>::borrow_info(&mut info);
+    }
+}
+
+unsafe impl<'a, T: Component<Tracking = track::All> + Send + Sync> BorrowInfo
+    for TrackedMut<'a, T>
+{
+    fn borrow_info(mut info: &mut Vec<info::TypeInfo>) {
+        UniqueViewMut::<'a, T>::borrow_info(&mut info);
+    }
+}
+
+/// Value of a tracked unique `T` when tracking was last reset.
+pub struct Previous<T>(pub T);
+
+impl<T: Send + Sync + 'static> Component for Previous<T> {
+    type Tracking = track::Untracked;
+}
+
+/// Read a tracked unique `T` along with its [Previous] value, to see what it changed from.
+///
+/// The previous value is taken at the end of every update, so a `T` modified several times in an update
+/// is compared with its value at the end of the last update.
+pub struct TrackedChange<'a, T: Component<Tracking = track::All>>(
+    UniqueView<'a, T>,
+    UniqueView<'a, Previous<T>>,
+);
+
+impl<T: Component<Tracking = track::All>> TrackedChange<'_, T> {
+    pub fn current(&self) -> &T {
+        &*self.0
+    }
+
+    pub fn previous(&self) -> &T {
+        &self.1 .0
+    }
+
+    /// `(previous, current)` if `T` was inserted or modified since tracking was last reset.
+    pub fn changed(&self) -> Option<(&T, &T)> {
+        if self.0.is_inserted_or_modified() {
+            Some((self.previous(), self.current()))
+        } else {
+            None
+        }
+    }
+}
+
+pub struct TrackedChangeBorrower<T>(T);
+
+impl<T> IntoBorrow for TrackedChange<'_, T>
+where
+    T: Send + Sync + Component<Tracking = track::All>,
+{
+    type Borrow = TrackedChangeBorrower<T>;
+}
+
+impl<'a, T> Borrow<'a> for TrackedChangeBorrower<T>
+where
+    T: Send + Sync + Component<Tracking = track::All>,
+{
+    type View = TrackedChange<'a, T>;
+
+    fn borrow(
+        world: &'a World,
+        last_run: Option<u32>,
+        current: u32,
+    ) -> Result<Self::View, error::GetStorage> {
+        Ok(TrackedChange(
+            <UniqueView<T> as IntoBorrow>::Borrow::borrow(world, last_run, current)?,
+            <UniqueView<Previous<T>> as IntoBorrow>::Borrow::borrow(world, last_run, current)?,
+        ))
+    }
+}
+
+unsafe impl<'a, T: Component<Tracking = track::All> + Send + Sync> BorrowInfo
+    for TrackedChange<'a, T>
+{
+    fn borrow_info(mut info: &mut Vec<info::TypeInfo>) {
+        UniqueView::<'a, T>::borrow_info(&mut info);
+        UniqueView::<'a, Previous<T>>::borrow_info(&mut info);
+    }
+}
+
+/// Label shared by the systems resetting tracked uniques, so snapshots can be ordered before them.
+pub(crate) const RESET_TRACKED_LABEL: &str = "tracked_unique::reset";
+
+pub(crate) fn snapshot_previous<T: Clone + Component<Tracking = track::All>>(
+    uv_tracked_unique_t: UniqueView<T>,
+    mut uvm_previous_t: UniqueViewMut<Previous<T>>,
+) {
+    if uv_tracked_unique_t.is_inserted_or_modified() {
+        let span = trace_span!("snapshot_previous", tracked = ?type_name::<T>());
+        let _span = span.enter();
+        uvm_previous_t.0 = (*uv_tracked_unique_t).clone();
     }
 }
 
@@ -28,3 +261,65 @@ pub(crate) fn reset_tracked_unique<T: Component<Tracking = track::All>>(
     let _span = span.enter();
     uvm_tracked_unique_t.clear_inserted_and_modified();
 }
+
+/// Clear the inserted and modified components of storage `T`, for [AppBuilder::add_reset_system]
+/// when no other system clears a storage made [AppBuilder::update_pack].
+pub fn reset_tracked_storage<T: Component<Tracking = track::All>>(mut vm_tracked_t: ViewMut<T>) {
+    let span = trace_span!("reset_tracked_storage", tracked = ?type_name::<T>());
+    let _span = span.enter();
+    vm_tracked_t.clear_all_inserted_and_modified();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Component, Default)]
+    #[track(All)]
+    struct Selected(Option<u32>);
+
+    #[test]
+    fn tracked_unique_plugin_resets_after_update() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(TrackedUniquePlugin::new(Selected(None)));
+        builder.finish();
+
+        assert!(app.run(|selected: Tracked<Selected>| selected.is_inserted()));
+        app.update();
+        assert!(!app.run(|selected: Tracked<Selected>| selected.is_inserted()));
+
+        app.run(|mut selected: TrackedMut<Selected>| selected.0 = Some(1));
+        assert!(app.run(|selected: Tracked<Selected>| selected.is_modified()));
+        app.update();
+        assert!(!app.run(|selected: Tracked<Selected>| selected.is_modified()));
+        assert_eq!(app.run(|selected: Tracked<Selected>| selected.0), Some(1));
+    }
+
+    #[test]
+    fn tracked_change_reads_value_at_last_reset() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(TrackedUniquePlugin::new(Selected(Some(1))));
+        builder.finish();
+        app.update();
+
+        app.run(|mut selected: TrackedMut<Selected>| selected.0 = Some(2));
+        app.run(|mut selected: TrackedMut<Selected>| selected.0 = Some(3));
+        assert_eq!(
+            app.run(|change: TrackedChange<Selected>| change
+                .changed()
+                .map(|(previous, current)| (previous.0, current.0))),
+            Some((Some(1), Some(3)))
+        );
+
+        app.update();
+        assert_eq!(
+            app.run(|change: TrackedChange<Selected>| (
+                change.previous().0,
+                change.changed().is_some()
+            )),
+            (Some(3), false)
+        );
+    }
+}
@@ -0,0 +1,2304 @@
+//! # Tree
+//!
+//! Tree provides a very simple way to manipulate the ordering of elements in a CRDT fashion enabling
+//! a minimal amount of effort to reorder entities and resolve multiple concurrent reorderings.
+//!
+//! This is intended to be used alongside an additional decorating system which can index the child positions
+//! in the tree whenever a movement occurs and to keep everything in check.
+//!
+//! So, you would have a tree_reordering system (for managing reordering commands operating on the tree components) and
+//! a tree_indexing system for updating the tree_index components.
+//!
+//! The Advantages to this approach are to
+//!  - simplify the source of truth for more reliable ser & de
+//!  - Reduce the size of the seriallized form
+//!  - Less blocking systems (if something only cares that the ChildOf / Ordering has changed and the system does not
+//!    look at the indexed outputs, then it can run concurrently with the tree_indexing system)
+//!
+//! [TreePlugin] registers both systems along with the [MoveCommands] unique, so pushing a [MoveCmd] is enough to move an entity.
+//!
+//! [TreePlugin::with_delete_policy] decides what happens to the children of deleted or unlinked nodes, see [DeletePolicy].
+//!
+//! [InheritedPlugin]`<T>` keeps [Inherited]`<T>`, the `T` of each node or its nearest ancestor, up to date.
+//!
+//! [verify_tree_indexes] compares the indexes with [ChildOf], [tree_index_rebuilding] rebuilds them,
+//! and [TreePlugin::with_verification] checks them after every update.
+//!
+//! [export_nested] and [import_nested] convert between trees of entities and [NestedNode]s.
+//!
+//! [diff_trees] turns two [TreeSnapshot]s into a [TreePatch] list, replayed with [MoveCommands::push_patch].
+//!
+//! [TreeView] walks the indexed tree from systems: ancestors, descendants, depth, roots and common ancestors.
+//!
+//! Every tree type is generic over a [Relation] marker, defaulting to [Hierarchy]. Adding a [TreePlugin] per relation
+//! keeps several independent trees over the same entities, e.g. `TreePlugin::<Layout>::default()` next to `TreePlugin::<Hierarchy>::default()`.
+//!
+//! [TreeReplicaPlugin] additionally records moves in a [MoveLog] tagged with a [ReplicaId] and Lamport [Timestamp],
+//! so replicas exchanging their [ReplicatedMove]s converge on the same tree. Replicas are expected to share [EntityId]s.
+use crate::*;
+
+mod cascading;
+mod diff;
+mod indexing;
+mod inheritance;
+mod nested;
+mod node;
+mod reordering;
+mod replication;
+mod traversal;
+mod verification;
+
+pub use cascading::{tree_cascading, DeletePolicy, TreeDeletePolicy};
+pub use diff::{diff_trees, snapshot_tree, TreePatch, TreeSnapshot};
+pub use indexing::{
+    tree_indexing, Depth, ParentIndex, Reindexed, SiblingIndex, TreeRoot, REBALANCE_KEY_LEN,
+};
+pub use inheritance::{tree_inheriting, Inherited, UpdateInherited};
+pub use nested::{export_nested, import_nested, NestedNode};
+pub use node::*;
+pub use reordering::{tree_reordering, MoveCmd, MoveError, MoveToPlace};
+pub use replication::{tree_replication, MoveLog, ReplicaId, ReplicatedMove, Timestamp};
+pub use traversal::{Ancestors, BreadthFirst, DepthFirst, TreeView};
+pub use verification::{
+    tree_index_rebuilding, tree_verifying, verify_tree_indexes, IndexDiscrepancy,
+};
+
+/// Moves applied by [tree_reordering] on the next update, pushed through its `Vec`
+#[derive(Debug, Default)]
+pub struct MoveCommands<R = Hierarchy> {
+    commands: Vec<MoveCmd>,
+    // ChildOf changes made by the last tree_reordering, in the order they were made
+    applied: Vec<(EntityId, Option<ChildOf<R>>)>,
+    relation: std::marker::PhantomData<R>,
+}
+
+impl<R: Relation> Component for MoveCommands<R> {
+    type Tracking = track::Untracked;
+}
+
+impl<R: Relation> MoveCommands<R> {
+    /// The [ChildOf] set (or deleted if [None]) by the last run of [tree_reordering], in the order the moves were applied
+    pub fn applied(&self) -> &[(EntityId, Option<ChildOf<R>>)] {
+        &self.applied
+    }
+}
+
+impl<R> std::ops::Deref for MoveCommands<R> {
+    type Target = Vec<MoveCmd>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.commands
+    }
+}
+
+impl<R> std::ops::DerefMut for MoveCommands<R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.commands
+    }
+}
+
+/// Moves rejected by [tree_reordering], kept until taken by whoever issued them
+#[derive(Debug, Default)]
+pub struct MoveErrors<R = Hierarchy> {
+    errors: Vec<MoveError>,
+    relation: std::marker::PhantomData<R>,
+}
+
+impl<R: Relation> Component for MoveErrors<R> {
+    type Tracking = track::Untracked;
+}
+
+impl<R> std::ops::Deref for MoveErrors<R> {
+    type Target = Vec<MoveError>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.errors
+    }
+}
+
+impl<R> std::ops::DerefMut for MoveErrors<R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.errors
+    }
+}
+
+/// Differences found by [tree_verifying] after the last update, when [TreePlugin::with_verification] is used
+#[derive(Debug, Default)]
+pub struct IndexDiscrepancies<R = Hierarchy> {
+    discrepancies: Vec<IndexDiscrepancy<R>>,
+    relation: std::marker::PhantomData<R>,
+}
+
+impl<R: Relation> Component for IndexDiscrepancies<R> {
+    type Tracking = track::Untracked;
+}
+
+impl<R> std::ops::Deref for IndexDiscrepancies<R> {
+    type Target = Vec<IndexDiscrepancy<R>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.discrepancies
+    }
+}
+
+impl<R> std::ops::DerefMut for IndexDiscrepancies<R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.discrepancies
+    }
+}
+
+/// Registers [tree_reordering] and [tree_indexing] for the tree of relation `R`,
+/// with the [MoveCommands] they read from and [MoveErrors] they report to
+pub struct TreePlugin<R = Hierarchy> {
+    delete_policy: DeletePolicy,
+    verify: bool,
+    relation: std::marker::PhantomData<R>,
+}
+
+impl<R: Relation> Default for TreePlugin<R> {
+    fn default() -> Self {
+        TreePlugin::new()
+    }
+}
+
+impl<R: Relation> TreePlugin<R> {
+    /// Plugin for the tree of relation `R`, same as [TreePlugin::default]
+    pub fn new() -> Self {
+        TreePlugin {
+            delete_policy: DeletePolicy::default(),
+            verify: false,
+            relation: std::marker::PhantomData,
+        }
+    }
+
+    /// Also register [tree_cascading], applying `delete_policy` to the children of nodes whose entity or [ChildOf]
+    /// is deleted. Moving a node to become a root, e.g. with [MoveToPlace::Unlink], keeps its subtree.
+    pub fn with_delete_policy(mut self, delete_policy: DeletePolicy) -> Self {
+        self.delete_policy = delete_policy;
+        self
+    }
+
+    /// Debug mode, also register [tree_verifying] to check the indexes against [ChildOf] after every update
+    pub fn with_verification(mut self) -> Self {
+        self.verify = true;
+        self
+    }
+}
+
+impl<R: Relation> Plugin for TreePlugin<R> {
+    fn build(&self, app: &mut AppBuilder) {
+        // needs direct update pack since TreePlugin clears updates on its own.
+        // labels are shared between relations, whose trees do not depend on each other.
+        app.update_pack::<ChildOf<R>>("update in response to ChildOf changes")
+            .add_unique(MoveCommands::<R>::default())
+            .add_unique(MoveErrors::<R>::default())
+            .add_unique(TreeDeletePolicy::<R>::new(self.delete_policy))
+            .add_system_with(
+                reordering::tree_reordering::<R>,
+                SystemConfig::labeled("tree::reordering").before("tree::indexing"),
+            )
+            .add_system_with(
+                indexing::tree_indexing::<R>,
+                SystemConfig::labeled("tree::indexing"),
+            );
+
+        if self.delete_policy != DeletePolicy::Keep {
+            app.add_system_with(
+                cascading::tree_cascading::<R>,
+                // before replication, which logs the cascaded ChildOf changes as local moves
+                SystemConfig::labeled("tree::cascading")
+                    .after("tree::reordering")
+                    .before("tree::replication")
+                    .before("tree::indexing"),
+            );
+        }
+
+        if self.verify {
+            app.add_unique(IndexDiscrepancies::<R>::default())
+                .add_system_with(
+                    verification::tree_verifying::<R>,
+                    SystemConfig::labeled("tree::verifying").after("tree::indexing"),
+                );
+        }
+    }
+}
+
+/// Registers [tree_inheriting] for `T` along the tree of relation `R` after [tree_indexing], building on [TreePlugin].
+///
+/// Inserted and modified `T` and [Inherited]`<T>` are cleared at the end of every update, see [reset_tracked_storage].
+pub struct InheritedPlugin<T, R = Hierarchy>(std::marker::PhantomData<(T, R)>);
+
+impl<T, R> Default for InheritedPlugin<T, R> {
+    fn default() -> Self {
+        InheritedPlugin(std::marker::PhantomData)
+    }
+}
+
+impl<T, R> Plugin for InheritedPlugin<T, R>
+where
+    T: Clone + PartialEq + Send + Sync + Component<Tracking = track::All>,
+    R: Relation,
+{
+    fn build(&self, app: &mut AppBuilder) {
+        app.update_pack::<T>("Inherited recomputes the subtrees of changed values")
+            .update_pack::<Inherited<T, R>>("Inherited is only written where the value changed")
+            .add_system_with(
+                inheritance::tree_inheriting::<T, R>,
+                SystemConfig::labeled("tree::inheriting").after("tree::indexing"),
+            )
+            .add_reset_system(
+                reset_tracked_storage::<T>,
+                "Inherited only recomputes for values changed since the last update",
+            )
+            .add_reset_system(
+                reset_tracked_storage::<Inherited<T, R>>,
+                "Inherited changes are reported for one update",
+            );
+    }
+
+    fn dependencies(&self) -> Vec<PluginDependency> {
+        vec![PluginDependency::new::<TreePlugin<R>>(
+            "inherits along the indexed tree",
+        )]
+    }
+}
+
+/// Moves of this replica, and moves of other replicas to merge on the next update
+#[derive(Debug)]
+pub struct TreeReplica<R = Hierarchy> {
+    pub log: MoveLog<R>,
+    /// Merged by [tree_replication] on the next update
+    pub incoming: Vec<ReplicatedMove<R>>,
+    /// Local moves recorded by [tree_replication], to be taken and sent to the other replicas
+    pub outgoing: Vec<ReplicatedMove<R>>,
+}
+
+impl<R: Relation> Component for TreeReplica<R> {
+    type Tracking = track::Untracked;
+}
+
+impl<R: Relation> TreeReplica<R> {
+    pub fn new(replica: ReplicaId) -> Self {
+        TreeReplica {
+            log: MoveLog::new(replica),
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+        }
+    }
+}
+
+/// Registers [tree_replication] with the [TreeReplica] unique for this replica of the tree of relation `R`,
+/// building on [TreePlugin].
+pub struct TreeReplicaPlugin<R = Hierarchy> {
+    replica: ReplicaId,
+    relation: std::marker::PhantomData<R>,
+}
+
+impl<R: Relation> TreeReplicaPlugin<R> {
+    pub fn new(replica: ReplicaId) -> Self {
+        TreeReplicaPlugin {
+            replica,
+            relation: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<R: Relation> Plugin for TreeReplicaPlugin<R> {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_unique(TreeReplica::<R>::new(self.replica))
+            .add_system_with(
+                replication::tree_replication::<R>,
+                SystemConfig::labeled("tree::replication")
+                    .after("tree::reordering")
+                    .before("tree::indexing"),
+            );
+    }
+
+    fn dependencies(&self) -> Vec<PluginDependency> {
+        vec![PluginDependency::new::<TreePlugin<R>>(
+            "replicates the moves of the tree",
+        )]
+    }
+}
+
+/// A spattering of tests to check things
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_world_with_index_system() -> World {
+        // Create a new world
+        let world = World::new();
+
+        // Add the indexing workload
+        let indexing = WorkloadBuilder::new("indexing");
+
+        indexing
+            .with_system(indexing::tree_indexing::<Hierarchy>)
+            .with_system(|mut vm_child_of: ViewMut<ChildOf>| {
+                vm_child_of.clear_all_inserted_and_modified();
+                vm_child_of.take_deleted();
+            })
+            .add_to_world(&world)
+            .unwrap();
+
+        world
+    }
+
+    #[test]
+    fn insert_single_entity_with_no_children() {
+        let world = setup_world_with_index_system();
+        // Add a parent and child entity
+        let a = world
+            .run(|mut entities: EntitiesViewMut| entities.add_entity((), ()))
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+
+        // There should be no indexes at all
+        world
+            .run(
+                |v_parent_index: View<ParentIndex>, v_sibling_index: View<SiblingIndex>| {
+                    assert_eq!(v_parent_index.contains(a), false);
+                    assert_eq!(v_sibling_index.contains(a), false);
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn insert_single_child() {
+        let world = setup_world_with_index_system();
+        // Add a parent and child entity
+        let (a, a1) = world
+            .run(
+                |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                    let a = entities.add_entity((), ());
+                    let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                    (a, a1)
+                },
+            )
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+
+        // Run an anonymous system to verify the state of the indexes
+        world
+            .run(
+                |v_parent_index: View<ParentIndex>, v_sibling_index: View<SiblingIndex>| {
+                    // The root node should not have a sibling index
+                    v_sibling_index
+                        .get(a)
+                        .expect_err("should not have sibling data");
+
+                    // The root node should have a parent index
+                    let c: &ParentIndex = v_parent_index.get(a).expect("has children");
+
+                    // The root node should have exactly one child
+                    assert_eq!(parent_children_ids(c), vec![a1], "should be in order");
+
+                    // The child should not have a parent index
+                    assert_eq!(v_parent_index.contains(a1), false);
+
+                    // The child should have a sibling index, with no siblings and the parent being the root node
+                    let a1_sib: &SiblingIndex =
+                        v_sibling_index.get(a1).expect("should have sibling data");
+                    assert_eq!(a1_sib.prev_sibling, None, "only child");
+                    assert_eq!(a1_sib.next_sibling, None, "only child");
+                    assert_eq!(a1_sib.parent_node, a)
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn insert_many_siblings() {
+        let world = setup_world_with_index_system();
+        // Add a parent entity with many children
+        let (a, a1, a2, a3) = world
+            .run(
+                |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                    let a = entities.add_entity((), ());
+                    let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                    let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 2));
+                    let a3 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 3));
+
+                    (a, a1, a2, a3)
+                },
+            )
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+
+        // Run an anonymous system to verify the state of the indexes
+        world
+            .run(
+                |v_parent_index: View<ParentIndex>, v_sibling_index: View<SiblingIndex>| {
+                    // The root node should not have a sibling index
+                    v_sibling_index
+                        .get(a)
+                        .expect_err("should not have sibling data");
+
+                    // The root node should have a parent index
+                    let c: &ParentIndex = v_parent_index.get(a).expect("has children");
+
+                    // The root node should have many children
+                    assert_eq!(
+                        parent_children_ids(c),
+                        vec![a1, a2, a3],
+                        "should be in order"
+                    );
+
+                    // The children should not have a parent indexes
+                    assert_eq!(v_parent_index.contains(a1), false);
+                    assert_eq!(v_parent_index.contains(a2), false);
+                    assert_eq!(v_parent_index.contains(a3), false);
+
+                    // The first sibling should not have a prev sibling but have a next sibling
+                    let a1_sib = v_sibling_index.get(a1).expect("should have sibling data");
+                    assert_eq!(a1_sib.prev_sibling, None, "only child");
+                    assert_eq!(a1_sib.next_sibling.as_ref().unwrap().1, a2, "has sibling");
+
+                    // The middle sibling should have both prev and next siblings
+                    let a2_sib = v_sibling_index.get(a2).expect("should have sibling data");
+                    assert_eq!(a2_sib.prev_sibling.as_ref().unwrap().1, a1, "has sibling");
+                    assert_eq!(a2_sib.next_sibling.as_ref().unwrap().1, a3, "has sibling");
+
+                    // The last sibling should have a prev sibling but not have a next sibling
+                    let a3_sib = v_sibling_index.get(a3).expect("should have sibling data");
+                    assert_eq!(a3_sib.prev_sibling.as_ref().unwrap().1, a2, "has sibling");
+                    assert_eq!(a3_sib.next_sibling, None, "only child");
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn insert_deep_children() {
+        let world = setup_world_with_index_system();
+        let (a, a1, a2, a3) = world
+            .run(
+                |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                    let a = entities.add_entity((), ());
+                    let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                    let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a1, 2));
+                    let a3 = entities.add_entity(&mut vm_child_of, ChildOf::new(a2, 3));
+
+                    (a, a1, a2, a3)
+                },
+            )
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+
+        // Run an anonymous system to verify the state of the indexes
+        world
+            .run(
+                |v_parent_index: View<ParentIndex>, v_sibling_index: View<SiblingIndex>| {
+                    // Each entity should have a parent index containing one child
+                    let a_pi: &ParentIndex = v_parent_index.get(a).expect("has children");
+                    assert_eq!(parent_children_ids(a_pi), vec![a1]);
+
+                    let a1_pi: &ParentIndex = v_parent_index.get(a1).expect("has children");
+                    assert_eq!(parent_children_ids(a1_pi), vec![a2]);
+
+                    let a2_pi: &ParentIndex = v_parent_index.get(a2).expect("has children");
+                    assert_eq!(parent_children_ids(a2_pi), vec![a3]);
+
+                    // The leaf entity should not have a parent index
+                    assert_eq!(v_parent_index.contains(a3), false);
+
+                    // Each non-root entity should have a sibling index that is empty
+                    let a1_sib = v_sibling_index.get(a1).expect("should have sibling data");
+                    assert_eq!(a1_sib.prev_sibling, None, "only child");
+                    assert_eq!(a1_sib.next_sibling, None, "only child");
+
+                    let a2_sib = v_sibling_index.get(a2).expect("should have sibling data");
+                    assert_eq!(a2_sib.prev_sibling, None, "only child");
+                    assert_eq!(a2_sib.next_sibling, None, "only child");
+
+                    let a3_sib = v_sibling_index.get(a3).expect("should have sibling data");
+                    assert_eq!(a3_sib.prev_sibling, None, "only child");
+                    assert_eq!(a3_sib.next_sibling, None, "only child");
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    // TODO: Complete this test
+    fn update_entity_as_child_of_self() {
+        let world = setup_world_with_index_system();
+        world
+            .run(
+                |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                    let a = entities.add_entity((), ());
+                    entities.add_component(a, &mut vm_child_of, ChildOf::new(a, 1));
+
+                    a
+                },
+            )
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+    }
+
+    #[test]
+    fn traversal_ends_on_cycles() {
+        let world = setup_world_with_index_system();
+        let (a, b, c) = world
+            .run(
+                |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                    let a = entities.add_entity((), ());
+                    entities.add_component(a, &mut vm_child_of, ChildOf::new(a, 1));
+                    let b = entities.add_entity((), ());
+                    let c = entities.add_entity(&mut vm_child_of, ChildOf::new(b, 1));
+                    entities.add_component(b, &mut vm_child_of, ChildOf::new(c, 1));
+
+                    (a, b, c)
+                },
+            )
+            .unwrap();
+
+        world.run_default().unwrap();
+
+        world
+            .run(|tree: TreeView| {
+                assert_eq!(tree.ancestors(a).count(), 0);
+                assert_eq!(tree.descendants_depth_first(a).count(), 0);
+                assert_eq!(tree.descendants_breadth_first(a).count(), 0);
+                assert_eq!(tree.root_of(a), a);
+
+                assert_eq!(tree.ancestors(b).collect::<Vec<_>>(), vec![c]);
+                assert_eq!(tree.descendants_depth_first(b).collect::<Vec<_>>(), vec![c]);
+                assert_eq!(
+                    tree.descendants_breadth_first(c).collect::<Vec<_>>(),
+                    vec![b]
+                );
+                assert_eq!(tree.path_from_root(b), vec![c, b]);
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn delete_only_child() {
+        let world = setup_world_with_index_system();
+        // Add a parent and child entity
+        let (a, a1) = world
+            .run(
+                |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                    let a = entities.add_entity((), ());
+                    let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+
+                    (a, a1)
+                },
+            )
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+
+        // Delete the child entity
+        world
+            .run(|mut vm_child_of: ViewMut<ChildOf>| {
+                // remove should not be used as it is untracked for whatever reason...
+                vm_child_of.delete(a1);
+            })
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+
+        world
+            .run(
+                |v_parent_index: View<ParentIndex>, v_sibling_index: View<SiblingIndex>| {
+                    // There should be no sibling index for the previous parent
+                    assert_eq!(v_sibling_index.contains(a), false);
+
+                    // TODO: Should there be a parent index any more with no children?
+                    // There should be a parent index with no children
+                    let c: &ParentIndex = v_parent_index.get(a).expect("has a parent index");
+                    assert_eq!(parent_children_ids(c), vec![]);
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn delete_first_and_last_siblings() {
+        let world = setup_world_with_index_system();
+        let (a, a1, a2, a3) = world
+            .run(
+                |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                    let a = entities.add_entity((), ());
+                    let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                    let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 2));
+                    let a3 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 3));
+
+                    (a, a1, a2, a3)
+                },
+            )
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+
+        // Delete the first and last siblings
+        world
+            .run(|mut vm_child_of: ViewMut<ChildOf>| {
+                vm_child_of.delete(a1);
+                vm_child_of.delete(a3);
+            })
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+
+        world
+            .run(
+                |v_parent_index: View<ParentIndex>, v_sibling_index: View<SiblingIndex>| {
+                    // The root should only have one child now
+                    let c: &ParentIndex = v_parent_index.get(a).expect("has a parent index");
+                    assert_eq!(parent_children_ids(c), vec![a2]);
+
+                    // The previously first and last siblings should no longer have an index
+                    assert_eq!(v_sibling_index.contains(a1), false);
+                    assert_eq!(v_sibling_index.contains(a3), false);
+
+                    // The previously middle node should now not have any siblings
+                    let a2_sib = v_sibling_index.get(a2).expect("should have sibling data");
+                    assert_eq!(a2_sib.prev_sibling, None, "only child");
+                    assert_eq!(a2_sib.next_sibling, None, "only child");
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn delete_middle_sibling() {
+        let world = setup_world_with_index_system();
+        let (a, a1, a2, a3) = world
+            .run(
+                |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                    let a = entities.add_entity((), ());
+                    let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                    let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 2));
+                    let a3 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 3));
+
+                    (a, a1, a2, a3)
+                },
+            )
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+
+        // Delete the first and last siblings
+        world
+            .run(|mut vm_child_of: ViewMut<ChildOf>| {
+                vm_child_of.delete(a2);
+            })
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+
+        world
+            .run(
+                |v_parent_index: View<ParentIndex>, v_sibling_index: View<SiblingIndex>| {
+                    // The root should have 2 children now
+                    let c: &ParentIndex = v_parent_index.get(a).expect("has a parent index");
+                    assert_eq!(parent_children_ids(c), vec![a1, a3]);
+
+                    // The previously middle sibling should no longer have an index
+                    assert_eq!(v_sibling_index.contains(a2), false);
+
+                    // The previously first and last siblings should now be connected
+                    let a1_sib = v_sibling_index.get(a1).expect("should have sibling data");
+                    assert_eq!(a1_sib.next_sibling.as_ref().unwrap().1, a3);
+
+                    let a3_sib = v_sibling_index.get(a3).expect("should have sibling data");
+                    assert_eq!(a3_sib.prev_sibling.as_ref().unwrap().1, a1);
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn delete_middle_ancestor() {
+        let world = setup_world_with_index_system();
+        let (a, a1, a2) = world
+            .run(
+                |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                    let a = entities.add_entity((), ());
+                    let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                    let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a1, 2));
+
+                    (a, a1, a2)
+                },
+            )
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+
+        // Delete the first and last siblings
+        world
+            .run(|mut vm_child_of: ViewMut<ChildOf>| {
+                vm_child_of.delete(a1);
+            })
+            .unwrap();
+
+        // Run the indexing workload
+        world.run_default().unwrap();
+
+        // without TreePlugin's DeletePolicy, descendants keep referencing the removed node (see the delete policy tests)
+        world
+            .run(
+                |v_parent_index: View<ParentIndex>, v_sibling_index: View<SiblingIndex>| {
+                    // The root now has no children
+                    let c: &ParentIndex = v_parent_index.get(a).expect("has a parent index");
+                    assert_eq!(parent_children_ids(c), vec![]);
+
+                    // The previous middle ancestor should no longer have a sibling index
+                    assert_eq!(v_parent_index.contains(a1), true);
+                    assert_eq!(v_sibling_index.contains(a1), false);
+
+                    // The previous grandchild still references its parent
+                    let a2_sib = v_sibling_index.get(a2).expect("should have sibling data");
+                    assert_eq!(a2_sib.parent_node, a1);
+
+                    // Potential test code for descendent deletion
+                    // The root should have no children
+                    // let c: &ParentIndex = v_parent_index.get(a).expect("has a parent index");
+                    // assert_eq!(parent_children_ids(c), vec![]);
+
+                    // The previous middle ancestor and its descendents should no longer have an index
+                    // assert_eq!(v_parent_index.contains(a1), false);
+                    // assert_eq!(v_sibling_index.contains(a1), false);
+
+                    // assert_eq!(v_parent_index.contains(a2), false);
+                    // assert_eq!(v_sibling_index.contains(a2), false);
+
+                    // Potential test code for reparenting:
+                    // The root should have the grandchild as a child now
+                    // let c: &ParentIndex = v_parent_index.get(a).expect("has a parent index");
+                    // assert_eq!(parent_children_ids(c), vec![a2]);
+
+                    // The previous middle ancestor should no longer have an index
+                    // assert_eq!(v_parent_index.contains(a1), false);
+                    // assert_eq!(v_sibling_index.contains(a1), false);
+
+                    // // The previous grandchild should now have the root as a parent
+                    // let a2_sib = v_sibling_index.get(a2).expect("should have sibling data");
+                    // assert_eq!(a2_sib.parent_node, a);
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_indexing() {
+        let app = App::new();
+
+        WorkloadBuilder::new("default")
+            .with_system(indexing::tree_indexing::<Hierarchy>)
+            .with_system(|mut vm_child_of: ViewMut<ChildOf>| {
+                vm_child_of.clear_all_inserted_and_modified();
+            })
+            .add_to_world(&app.world)
+            .unwrap();
+
+        test_with_indexing_with_world(app);
+    }
+
+    #[test]
+    fn test_indexing_with_plugin() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(TreePlugin::<Hierarchy>::default());
+        builder.finish();
+        test_with_indexing_with_world(app);
+    }
+
+    fn test_with_indexing_with_world(app: App) {
+        let (a, a1, a2, a3, a6) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a = entities.add_entity((), ());
+                let a6 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 6));
+                let a3 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 3));
+                let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 2));
+                (a, a1, a2, a3, a6)
+            },
+        );
+
+        app.update();
+
+        app.run(
+            |v_parent_index: View<ParentIndex>, v_sibling_index: View<SiblingIndex>| {
+                v_sibling_index
+                    .get(a)
+                    .expect_err("should not have sibling data");
+
+                let c: &ParentIndex = v_parent_index.get(a).expect("has children");
+
+                assert_eq!(
+                    parent_children_ids(c),
+                    vec![a1, a2, a3, a6],
+                    "should be in order"
+                );
+            },
+        );
+
+        let (a1b, a1b1, a0, a4, a7) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a7 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 7));
+                let a0 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 0));
+                let a4 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 4));
+                let a1b = entities.add_entity(&mut vm_child_of, ChildOf::new(a1, 4));
+                let a1b1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a1b, 1));
+                (a1b, a1b1, a0, a4, a7)
+            },
+        );
+
+        app.update();
+
+        app.run(
+            |v_parent_index: View<ParentIndex>, v_sibling_index: View<SiblingIndex>| {
+                v_sibling_index
+                    .get(a)
+                    .expect_err("should not have sibling data");
+
+                let a1b_sib: &SiblingIndex =
+                    v_sibling_index.get(a1b).expect("should have sibling data");
+
+                assert_eq!(a1b_sib.next_sibling, None, "only child");
+                assert_eq!(a1b_sib.prev_sibling, None, "only child");
+
+                assert_eq!(
+                    parent_children_ids(v_parent_index.get(a1b).expect("has children")),
+                    vec![a1b1],
+                    "should have the one child"
+                );
+
+                assert_eq!(
+                    parent_children_ids(v_parent_index.get(a).expect("has children")),
+                    vec![a0, a1, a2, a3, a4, a6, a7],
+                    "should be in order"
+                );
+            },
+        );
+
+        // test removing and deleting ChildOf components
+
+        app.run(|mut vm_child_of: ViewMut<ChildOf>| {
+            // remove should not be used as it is untracked for whatever reason...
+            vm_child_of.delete(a7);
+            vm_child_of.delete(a4);
+            vm_child_of.delete(a0);
+            vm_child_of.delete(a1b);
+        });
+
+        app.update();
+
+        app.run(
+            |v_parent_index: View<ParentIndex>, v_sibling_index: View<SiblingIndex>| {
+                v_sibling_index
+                    .get(a1b)
+                    .expect_err("should not have sibling data");
+
+                assert_eq!(
+                    parent_children_ids(v_parent_index.get(a1b).expect("has children")),
+                    vec![a1b1],
+                    "should have the one child"
+                );
+
+                assert_eq!(
+                    parent_children_ids(v_parent_index.get(a).expect("has children")),
+                    vec![a1, a2, a3, a6],
+                    "should be in order without deleted entities"
+                );
+            },
+        );
+
+        // test inserting & overwriting after deleting
+
+        // a2 updated to ord 7, a1 child of a8
+        let (a8,) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                (&mut vm_child_of).get(a2).unwrap().1 = Ordered::hinted(7);
+                let a8 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 8));
+                (&mut vm_child_of).get(a1).unwrap().0 = a8;
+
+                (a8,)
+            },
+        );
+
+        app.update();
+
+        app.run(|v_parent_index: View<ParentIndex>| {
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a).expect("has children")),
+                vec![a3, a6, a2, a8],
+                "should be in order with updated entities"
+            );
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a8).expect("has children")),
+                vec![a1],
+                "should have modified parent"
+            );
+        });
+    }
+
+    #[test]
+    fn move_commands_with_plugin() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(TreePlugin::<Hierarchy>::default());
+        builder.finish();
+
+        let (a, a1, a2, b) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a = entities.add_entity((), ());
+                let b = entities.add_entity((), ());
+                let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 2));
+                (a, a1, a2, b)
+            },
+        );
+
+        app.update();
+
+        app.run(|mut commands: UniqueViewMut<MoveCommands>| {
+            commands.push(MoveCmd {
+                target: a1,
+                place: MoveToPlace::LastChildOf(b),
+            });
+        });
+
+        app.update();
+
+        app.run(|v_parent_index: View<ParentIndex>| {
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a).expect("has children")),
+                vec![a2],
+                "moved child should be unlinked from its previous parent"
+            );
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(b).expect("has children")),
+                vec![a1],
+                "moved child should be indexed under its new parent"
+            );
+        });
+    }
+
+    fn setup_app_with_plugin() -> App {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(TreePlugin::<Hierarchy>::default());
+        builder.finish();
+        app
+    }
+
+    fn apply_moves(app: &App, moves: Vec<MoveCmd>) -> Vec<MoveError> {
+        app.run(|mut commands: UniqueViewMut<MoveCommands>| commands.extend(moves));
+        app.update();
+        app.run(|mut errors: UniqueViewMut<MoveErrors>| std::mem::take(&mut **errors))
+    }
+
+    #[test]
+    fn move_next_to_siblings_sharing_a_key() {
+        let app = setup_app_with_plugin();
+        let (a, a1, a2, b1, b2) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a = entities.add_entity((), ());
+                let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                // created last, so they sort after both when given the same key
+                let b1 = entities.add_entity((), ());
+                let b2 = entities.add_entity((), ());
+                (a, a1, a2, b1, b2)
+            },
+        );
+        app.update();
+
+        let errors = apply_moves(
+            &app,
+            vec![
+                MoveCmd {
+                    target: b1,
+                    place: MoveToPlace::After(a1),
+                },
+                MoveCmd {
+                    target: b2,
+                    place: MoveToPlace::Before(a2),
+                },
+            ],
+        );
+
+        assert_eq!(errors, vec![]);
+        app.run(|v_parent_index: View<ParentIndex>| {
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a).expect("has children")),
+                vec![a1, b1, b2, a2]
+            );
+        });
+    }
+
+    #[test]
+    fn move_after_sibling() {
+        let app = setup_app_with_plugin();
+        let (a, a1, a2, a3) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a = entities.add_entity((), ());
+                let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 2));
+                let a3 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 3));
+                (a, a1, a2, a3)
+            },
+        );
+        app.update();
+
+        let errors = apply_moves(
+            &app,
+            vec![MoveCmd {
+                target: a1,
+                place: MoveToPlace::After(a2),
+            }],
+        );
+
+        assert_eq!(errors, vec![]);
+        app.run(|v_parent_index: View<ParentIndex>| {
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a).expect("has children")),
+                vec![a2, a1, a3]
+            );
+        });
+    }
+
+    #[test]
+    fn move_before_sibling() {
+        let app = setup_app_with_plugin();
+        let [a, a1, a2, a11, a12] = add_small_tree(&app);
+
+        let errors = apply_moves(
+            &app,
+            vec![
+                MoveCmd {
+                    target: a2,
+                    place: MoveToPlace::Before(a1),
+                },
+                MoveCmd {
+                    target: a12,
+                    place: MoveToPlace::Before(a11),
+                },
+            ],
+        );
+
+        assert_eq!(errors, vec![]);
+        app.run(|v_parent_index: View<ParentIndex>| {
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a).expect("has children")),
+                vec![a2, a1]
+            );
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a1).expect("has children")),
+                vec![a12, a11]
+            );
+        });
+    }
+
+    #[test]
+    fn move_to_index() {
+        let app = setup_app_with_plugin();
+        let (a, a1, a2, a3, b) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a = entities.add_entity((), ());
+                let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 2));
+                let a3 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 3));
+                let b = entities.add_entity((), ());
+                (a, a1, a2, a3, b)
+            },
+        );
+        app.update();
+
+        let errors = apply_moves(
+            &app,
+            vec![
+                // index among the other children
+                MoveCmd {
+                    target: a1,
+                    place: MoveToPlace::AtIndex(a, 1),
+                },
+                MoveCmd {
+                    target: b,
+                    place: MoveToPlace::AtIndex(a, 0),
+                },
+                // past the end is last
+                MoveCmd {
+                    target: a2,
+                    place: MoveToPlace::AtIndex(a, 10),
+                },
+            ],
+        );
+
+        assert_eq!(errors, vec![]);
+        app.run(|v_parent_index: View<ParentIndex>| {
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a).expect("has children")),
+                vec![b, a1, a3, a2]
+            );
+        });
+    }
+
+    #[test]
+    fn swap_places() {
+        let app = setup_app_with_plugin();
+        let [a, a1, a2, a11, a12] = add_small_tree(&app);
+        let b = app.run(|mut entities: EntitiesViewMut| entities.add_entity((), ()));
+
+        let errors = apply_moves(
+            &app,
+            vec![
+                MoveCmd {
+                    target: a11,
+                    place: MoveToPlace::Swap(a12),
+                },
+                MoveCmd {
+                    target: a2,
+                    place: MoveToPlace::Swap(a12),
+                },
+                // b is a root, so a11 becomes one
+                MoveCmd {
+                    target: a11,
+                    place: MoveToPlace::Swap(b),
+                },
+            ],
+        );
+
+        assert_eq!(errors, vec![]);
+        app.run(|v_parent_index: View<ParentIndex>, tree: TreeView| {
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a).expect("has children")),
+                vec![a1, a12]
+            );
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a1).expect("has children")),
+                vec![a2, b]
+            );
+            assert_eq!(tree.parent(a11), None);
+        });
+
+        let swap_with_descendant = MoveCmd {
+            target: a,
+            place: MoveToPlace::Swap(a2),
+        };
+        assert_eq!(
+            apply_moves(&app, vec![swap_with_descendant.clone()]),
+            vec![MoveError::CreatesCycle {
+                cmd: swap_with_descendant,
+                parent: a1,
+            }]
+        );
+    }
+
+    #[test]
+    fn swap_with_root_keeps_subtree() {
+        let (app, [a, a1, a2, a11, a12]) =
+            setup_app_with_delete_policy(DeletePolicy::DeleteDescendants);
+        let b = app.run(|mut entities: EntitiesViewMut| entities.add_entity((), ()));
+
+        // a1 becomes a root along with its children, which are not deleted
+        let errors = apply_moves(
+            &app,
+            vec![MoveCmd {
+                target: a1,
+                place: MoveToPlace::Swap(b),
+            }],
+        );
+
+        assert_eq!(errors, vec![]);
+        app.run(|v_entities: EntitiesView, tree: TreeView| {
+            assert!(v_entities.is_alive(a11));
+            assert!(v_entities.is_alive(a12));
+            assert_eq!(tree.parent(a1), None);
+            assert_eq!(tree.children(a1).collect::<Vec<_>>(), vec![a11, a12]);
+            assert_eq!(tree.children(a).collect::<Vec<_>>(), vec![b, a2]);
+        });
+    }
+
+    #[test]
+    fn moves_in_one_update_build_on_each_other() {
+        let app = setup_app_with_plugin();
+        let (a, a1, a2, a3, b) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a = entities.add_entity((), ());
+                let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 2));
+                let a3 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 3));
+                let b = entities.add_entity((), ());
+                (a, a1, a2, a3, b)
+            },
+        );
+        app.update();
+
+        let errors = apply_moves(
+            &app,
+            vec![
+                MoveCmd {
+                    target: a3,
+                    place: MoveToPlace::FirstChildOf(a),
+                },
+                // relies on a3 being indexed first
+                MoveCmd {
+                    target: a2,
+                    place: MoveToPlace::After(a3),
+                },
+                MoveCmd {
+                    target: a1,
+                    place: MoveToPlace::LastChildOf(b),
+                },
+                // relies on a1 being indexed under b
+                MoveCmd {
+                    target: a3,
+                    place: MoveToPlace::After(a1),
+                },
+            ],
+        );
+
+        assert_eq!(errors, vec![]);
+        app.run(|v_parent_index: View<ParentIndex>| {
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a).expect("has children")),
+                vec![a2]
+            );
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(b).expect("has children")),
+                vec![a1, a3]
+            );
+        });
+    }
+
+    #[test]
+    fn collapsed_keys_are_rebalanced() {
+        let app = setup_app_with_plugin();
+        let mut long_key = Ordered::hinted(1);
+        for _ in 0..200 {
+            long_key = long_key.before();
+        }
+        assert!(long_key.as_bytes().len() > REBALANCE_KEY_LEN);
+
+        let (a, a1, a2, a3) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a = entities.add_entity((), ());
+                let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 5));
+                let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 5));
+                let a3 =
+                    entities.add_entity(&mut vm_child_of, ChildOf::ordered(a, long_key.clone()));
+                (a, a1, a2, a3)
+            },
+        );
+
+        app.update();
+
+        app.run(
+            |v_child_of: View<ChildOf>,
+             v_parent_index: View<ParentIndex>,
+             v_sibling_index: View<SiblingIndex>| {
+                let parent_index = v_parent_index.get(a).expect("has children");
+                // equal keys are ordered by EntityId
+                let mut equal_keys = vec![a1, a2];
+                equal_keys.sort();
+                assert_eq!(
+                    parent_children_ids(parent_index),
+                    [vec![a3], equal_keys].concat()
+                );
+
+                let children = parent_index.children.iter().collect::<Vec<_>>();
+                assert!(children.windows(2).all(|pair| pair[0].0 < pair[1].0));
+                for (ordered, id) in children.iter() {
+                    assert!(ordered.as_bytes().len() <= REBALANCE_KEY_LEN);
+                    assert_eq!(&v_child_of.get(*id).unwrap().1, ordered);
+                    assert_eq!(
+                        v_sibling_index.get(*id).unwrap().ordered_node,
+                        (ordered.clone(), *id)
+                    );
+                }
+
+                // the new keys are left as changes for the next update
+                assert_eq!(v_child_of.modified().iter().count(), 3);
+            },
+        );
+    }
+
+    fn setup_replica(replica: ReplicaId) -> (App, Vec<EntityId>) {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(TreeReplicaPlugin::<Hierarchy>::new(replica));
+        builder.finish();
+        // replicas create the same entities in the same order, so they share ids
+        let ids = app.run(|mut entities: EntitiesViewMut| {
+            (0..3).map(|_| entities.add_entity((), ())).collect()
+        });
+        (app, ids)
+    }
+
+    fn exchange_moves(from: &App, to: &App) {
+        let moves = from
+            .run(|mut replica: UniqueViewMut<TreeReplica>| std::mem::take(&mut replica.outgoing));
+        to.run(|mut replica: UniqueViewMut<TreeReplica>| replica.incoming.extend(moves));
+    }
+
+    #[test]
+    fn replicate_moves_in_the_order_they_were_made() {
+        let (app_1, ids) = setup_replica(1);
+        let (app_2, _) = setup_replica(2);
+        let (root, a, b) = (ids[0], ids[1], ids[2]);
+        assert!(a < b);
+
+        apply_moves(
+            &app_1,
+            vec![
+                MoveCmd {
+                    target: a,
+                    place: MoveToPlace::LastChildOf(root),
+                },
+                MoveCmd {
+                    target: b,
+                    place: MoveToPlace::LastChildOf(a),
+                },
+            ],
+        );
+        // a moves under its former child, which would be a cycle if recorded before unlinking b
+        let errors = apply_moves(
+            &app_1,
+            vec![
+                MoveCmd {
+                    target: b,
+                    place: MoveToPlace::Unlink,
+                },
+                MoveCmd {
+                    target: a,
+                    place: MoveToPlace::LastChildOf(b),
+                },
+            ],
+        );
+        assert_eq!(errors, vec![]);
+
+        let outgoing = app_1.run(|replica: UniqueView<TreeReplica>| {
+            replica
+                .outgoing
+                .iter()
+                .map(|replicated| {
+                    (
+                        replicated.target,
+                        replicated.child_of.as_ref().map(|child_of| child_of.0),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            outgoing,
+            vec![(a, Some(root)), (b, Some(a)), (b, None), (a, Some(b))]
+        );
+
+        exchange_moves(&app_1, &app_2);
+        app_2.update();
+        for app in [&app_1, &app_2].iter() {
+            app.run(
+                |v_parent_index: View<ParentIndex>, v_child_of: View<ChildOf>| {
+                    assert_eq!(parent_children_ids(v_parent_index.get(b).unwrap()), vec![a]);
+                    assert!(v_child_of.get(b).is_err());
+                    assert_eq!(v_child_of.get(a).unwrap().0, b);
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn replicas_converge_through_plugin() {
+        let (app_1, ids) = setup_replica(1);
+        let (app_2, ids_2) = setup_replica(2);
+        assert_eq!(ids, ids_2);
+        let (root, a, b) = (ids[0], ids[1], ids[2]);
+
+        apply_moves(
+            &app_1,
+            vec![
+                MoveCmd {
+                    target: a,
+                    place: MoveToPlace::LastChildOf(root),
+                },
+                MoveCmd {
+                    target: b,
+                    place: MoveToPlace::LastChildOf(root),
+                },
+            ],
+        );
+        exchange_moves(&app_1, &app_2);
+        app_2.update();
+
+        // concurrently moving a and b under each other, only the earlier move survives
+        apply_moves(
+            &app_1,
+            vec![MoveCmd {
+                target: a,
+                place: MoveToPlace::LastChildOf(b),
+            }],
+        );
+        apply_moves(
+            &app_2,
+            vec![MoveCmd {
+                target: b,
+                place: MoveToPlace::LastChildOf(a),
+            }],
+        );
+        exchange_moves(&app_1, &app_2);
+        exchange_moves(&app_2, &app_1);
+        app_1.update();
+        app_2.update();
+
+        for app in [&app_1, &app_2].iter() {
+            app.run(
+                |v_parent_index: View<ParentIndex>, v_child_of: View<ChildOf>| {
+                    assert_eq!(
+                        parent_children_ids(v_parent_index.get(root).unwrap()),
+                        vec![b]
+                    );
+                    assert_eq!(parent_children_ids(v_parent_index.get(b).unwrap()), vec![a]);
+                    assert_eq!(v_child_of.get(b).unwrap().0, root);
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn traverse_with_tree_view() {
+        let app = setup_app_with_plugin();
+        // a
+        // ├── a1
+        // │   ├── a11
+        // │   └── a12
+        // └── a2
+        //     └── a21
+        // b
+        let (a, a1, a11, a12, a2, a21, b) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a = entities.add_entity((), ());
+                let b = entities.add_entity((), ());
+                let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 2));
+                let a12 = entities.add_entity(&mut vm_child_of, ChildOf::new(a1, 2));
+                let a11 = entities.add_entity(&mut vm_child_of, ChildOf::new(a1, 1));
+                let a21 = entities.add_entity(&mut vm_child_of, ChildOf::new(a2, 1));
+                (a, a1, a11, a12, a2, a21, b)
+            },
+        );
+
+        app.update();
+
+        app.run(|tree: TreeView| {
+            assert_eq!(tree.parent(a11), Some(a1));
+            assert_eq!(tree.parent(a), None);
+            assert_eq!(tree.children(a1).collect::<Vec<_>>(), vec![a11, a12]);
+            assert_eq!(tree.children(b).count(), 0);
+
+            assert_eq!(tree.ancestors(a21).collect::<Vec<_>>(), vec![a2, a]);
+            assert_eq!(
+                tree.descendants_depth_first(a).collect::<Vec<_>>(),
+                vec![a1, a11, a12, a2, a21]
+            );
+            assert_eq!(
+                tree.descendants_breadth_first(a).collect::<Vec<_>>(),
+                vec![a1, a2, a11, a12, a21]
+            );
+
+            assert_eq!(tree.depth_of(a), 0);
+            assert_eq!(tree.depth_of(a12), 2);
+            assert_eq!(tree.root_of(a21), a);
+            assert_eq!(tree.root_of(b), b);
+            assert_eq!(tree.path_from_root(a12), vec![a, a1, a12]);
+
+            assert_eq!(tree.lowest_common_ancestor(a11, a12), Some(a1));
+            assert_eq!(tree.lowest_common_ancestor(a11, a21), Some(a));
+            assert_eq!(tree.lowest_common_ancestor(a1, a11), Some(a1));
+            assert_eq!(tree.lowest_common_ancestor(a11, b), None);
+        });
+    }
+
+    fn setup_app_with_delete_policy(delete_policy: DeletePolicy) -> (App, [EntityId; 5]) {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(TreePlugin::<Hierarchy>::default().with_delete_policy(delete_policy));
+        builder.finish();
+        let ids = add_small_tree(&app);
+        (app, ids)
+    }
+
+    fn add_small_tree(app: &App) -> [EntityId; 5] {
+        // a
+        // ├── a1
+        // │   ├── a11
+        // │   └── a12
+        // └── a2
+        let ids = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a = entities.add_entity((), ());
+                let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                let a2 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 2));
+                let a11 = entities.add_entity(&mut vm_child_of, ChildOf::new(a1, 1));
+                let a12 = entities.add_entity(&mut vm_child_of, ChildOf::new(a1, 2));
+                [a, a1, a2, a11, a12]
+            },
+        );
+        app.update();
+        ids
+    }
+
+    #[test]
+    fn delete_descendants_of_deleted_node() {
+        let (app, [a, a1, a2, a11, a12]) =
+            setup_app_with_delete_policy(DeletePolicy::DeleteDescendants);
+
+        app.run(|mut all_storages: AllStoragesViewMut| {
+            all_storages.delete_entity(a1);
+        });
+        app.update();
+
+        app.run(|v_entities: EntitiesView, tree: TreeView| {
+            assert!(!v_entities.is_alive(a11));
+            assert!(!v_entities.is_alive(a12));
+            assert!(v_entities.is_alive(a2));
+            assert_eq!(tree.children(a).collect::<Vec<_>>(), vec![a2]);
+        });
+    }
+
+    #[test]
+    fn reparent_children_of_deleted_node() {
+        let (app, [a, a1, a2, a11, a12]) =
+            setup_app_with_delete_policy(DeletePolicy::ReparentToGrandparent);
+
+        app.run(|mut all_storages: AllStoragesViewMut| {
+            all_storages.delete_entity(a1);
+        });
+        app.update();
+
+        app.run(|v_child_of: View<ChildOf>, tree: TreeView| {
+            // in place of a1, keeping their order
+            assert_eq!(tree.children(a).collect::<Vec<_>>(), vec![a11, a12, a2]);
+            assert_eq!(v_child_of.get(a11).unwrap().0, a);
+            assert_eq!(v_child_of.get(a12).unwrap().0, a);
+        });
+    }
+
+    #[test]
+    fn delete_descendants_of_unlinked_node() {
+        let (app, [a, a1, a2, a11, a12]) =
+            setup_app_with_delete_policy(DeletePolicy::DeleteDescendants);
+
+        app.run(|mut vm_child_of: ViewMut<ChildOf>| {
+            vm_child_of.delete(a1);
+        });
+        app.update();
+
+        app.run(|v_entities: EntitiesView, tree: TreeView| {
+            // the unlinked node itself stays, as a root
+            assert!(v_entities.is_alive(a1));
+            assert!(!v_entities.is_alive(a11));
+            assert!(!v_entities.is_alive(a12));
+            assert_eq!(tree.children(a).collect::<Vec<_>>(), vec![a2]);
+            assert_eq!(tree.children(a1).count(), 0);
+        });
+    }
+
+    #[test]
+    fn reparent_children_of_unlinked_node() {
+        let (app, [a, a1, a2, a11, a12]) =
+            setup_app_with_delete_policy(DeletePolicy::ReparentToGrandparent);
+
+        app.run(|mut vm_child_of: ViewMut<ChildOf>| {
+            vm_child_of.delete(a1);
+        });
+        app.update();
+
+        app.run(|v_child_of: View<ChildOf>, tree: TreeView| {
+            assert_eq!(tree.children(a).collect::<Vec<_>>(), vec![a11, a12, a2]);
+            assert!(v_child_of.get(a1).is_err());
+            assert_eq!(tree.children(a1).count(), 0);
+        });
+    }
+
+    #[test]
+    fn unlinked_node_keeps_its_subtree() {
+        let (app, [a, a1, a2, a11, a12]) =
+            setup_app_with_delete_policy(DeletePolicy::DeleteDescendants);
+
+        apply_moves(
+            &app,
+            vec![MoveCmd {
+                target: a1,
+                place: MoveToPlace::Unlink,
+            }],
+        );
+        app.run(|mut vm_child_of: ViewMut<ChildOf>| {
+            vm_child_of.delete(a2);
+        });
+        app.update();
+
+        app.run(|v_entities: EntitiesView, tree: TreeView| {
+            assert!(v_entities.is_alive(a11));
+            assert!(v_entities.is_alive(a12));
+            assert_eq!(tree.children(a).count(), 0);
+            assert_eq!(tree.children(a1).collect::<Vec<_>>(), vec![a11, a12]);
+            assert_eq!(tree.root_of(a12), a1);
+            assert_eq!(tree.root_of(a2), a2);
+        });
+    }
+
+    #[test]
+    fn detach_children_of_deleted_root() {
+        let (app, [a, a1, a2, a11, a12]) = setup_app_with_delete_policy(DeletePolicy::Detach);
+
+        app.run(|mut all_storages: AllStoragesViewMut| {
+            all_storages.delete_entity(a);
+        });
+        app.update();
+
+        app.run(|v_child_of: View<ChildOf>, tree: TreeView| {
+            assert!(!v_child_of.contains(a1));
+            assert!(!v_child_of.contains(a2));
+            assert_eq!(tree.root_of(a11), a1);
+            assert_eq!(tree.children(a1).collect::<Vec<_>>(), vec![a11, a12]);
+        });
+    }
+
+    #[test]
+    fn depth_and_root_follow_moves() {
+        let (app, [a, a1, a2, a11, a12]) = setup_app_with_delete_policy(DeletePolicy::Keep);
+        let depth_and_root = |id: EntityId| {
+            app.run(|v_depth: View<Depth>, v_tree_root: View<TreeRoot>| {
+                (
+                    v_depth.get(id).ok().copied(),
+                    v_tree_root.get(id).ok().copied(),
+                )
+            })
+        };
+        assert_eq!(
+            depth_and_root(a),
+            (Some(Depth::new(0)), Some(TreeRoot::new(a)))
+        );
+        assert_eq!(
+            depth_and_root(a1),
+            (Some(Depth::new(1)), Some(TreeRoot::new(a)))
+        );
+        assert_eq!(
+            depth_and_root(a12),
+            (Some(Depth::new(2)), Some(TreeRoot::new(a)))
+        );
+
+        // moving a subtree updates all of its nodes
+        apply_moves(
+            &app,
+            vec![MoveCmd {
+                target: a1,
+                place: MoveToPlace::LastChildOf(a2),
+            }],
+        );
+        assert_eq!(
+            depth_and_root(a1),
+            (Some(Depth::new(2)), Some(TreeRoot::new(a)))
+        );
+        assert_eq!(
+            depth_and_root(a11),
+            (Some(Depth::new(3)), Some(TreeRoot::new(a)))
+        );
+
+        // unlinking makes a new root, and leaves unlinked leaves without a depth
+        apply_moves(
+            &app,
+            vec![
+                MoveCmd {
+                    target: a1,
+                    place: MoveToPlace::Unlink,
+                },
+                MoveCmd {
+                    target: a11,
+                    place: MoveToPlace::Unlink,
+                },
+            ],
+        );
+        assert_eq!(
+            depth_and_root(a1),
+            (Some(Depth::new(0)), Some(TreeRoot::new(a1)))
+        );
+        assert_eq!(
+            depth_and_root(a12),
+            (Some(Depth::new(1)), Some(TreeRoot::new(a1)))
+        );
+        assert_eq!(depth_and_root(a11), (None, None));
+        // a2 keeps its (now empty) ParentIndex
+        assert_eq!(
+            depth_and_root(a2),
+            (Some(Depth::new(1)), Some(TreeRoot::new(a)))
+        );
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Component)]
+    #[track(All)]
+    struct Visible(bool);
+
+    #[test]
+    fn inherit_from_nearest_ancestor() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(InheritedPlugin::<Visible>::default());
+        builder.finish();
+        let [a, a1, a2, a11, a12] = add_small_tree(&app);
+
+        let inherited = |id: EntityId| {
+            app.run(|v_inherited: View<Inherited<Visible>>| {
+                v_inherited.get(id).ok().map(|inherited| inherited.0)
+            })
+        };
+
+        app.run(|entities: EntitiesView, mut vm_visible: ViewMut<Visible>| {
+            entities.add_component(a, &mut vm_visible, Visible(false));
+            entities.add_component(a11, &mut vm_visible, Visible(true));
+        });
+        app.update();
+        assert_eq!(inherited(a), Some(false));
+        assert_eq!(inherited(a1), Some(false));
+        assert_eq!(inherited(a11), Some(true));
+        assert_eq!(inherited(a12), Some(false));
+        assert_eq!(inherited(a2), Some(false));
+        // cleared at the end of the update, so only new changes are inherited next
+        app.run(|v_visible: View<Visible>| {
+            assert_eq!(v_visible.inserted_or_modified().iter().count(), 0);
+        });
+
+        app.run(|mut vm_visible: ViewMut<Visible>| {
+            (&mut vm_visible).get(a).unwrap().0 = true;
+            vm_visible.delete(a11);
+        });
+        app.update();
+        assert_eq!(inherited(a12), Some(true));
+        assert_eq!(inherited(a11), Some(true));
+
+        // unlinked subtrees no longer inherit
+        apply_moves(
+            &app,
+            vec![MoveCmd {
+                target: a1,
+                place: MoveToPlace::Unlink,
+            }],
+        );
+        assert_eq!(inherited(a1), None);
+        assert_eq!(inherited(a12), None);
+        assert_eq!(inherited(a2), Some(true));
+    }
+
+    #[derive(Component, Default)]
+    struct InheritedChanges(Vec<EntityId>);
+
+    fn record_inherited_changes(
+        v_inherited: View<Inherited<Visible>>,
+        mut changes: UniqueViewMut<InheritedChanges>,
+    ) {
+        changes.0 = v_inherited
+            .inserted_or_modified()
+            .iter()
+            .with_id()
+            .map(|(id, _)| id)
+            .collect();
+        changes.0.sort();
+    }
+
+    #[test]
+    fn only_changed_inherited_values_are_tracked() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder
+            .add_plugin(InheritedPlugin::<Visible>::default())
+            .add_unique(InheritedChanges::default())
+            .add_system_with(
+                record_inherited_changes,
+                SystemConfig::labeled("record").after("tree::inheriting"),
+            );
+        builder.finish();
+        let [a, a1, a2, a11, a12] = add_small_tree(&app);
+        let changes = || app.run(|changes: UniqueView<InheritedChanges>| changes.0.clone());
+        let mut all = vec![a, a1, a2, a11, a12];
+        all.sort();
+
+        app.run(|entities: EntitiesView, mut vm_visible: ViewMut<Visible>| {
+            entities.add_component(a, &mut vm_visible, Visible(false));
+        });
+        app.update();
+        assert_eq!(changes(), all);
+
+        // rewriting the same value recomputes the subtree without changing it
+        app.run(|mut vm_visible: ViewMut<Visible>| {
+            (&mut vm_visible).get(a).unwrap().0 = false;
+        });
+        app.update();
+        assert_eq!(changes(), vec![]);
+
+        app.run(|mut vm_visible: ViewMut<Visible>| {
+            (&mut vm_visible).get(a).unwrap().0 = true;
+        });
+        app.update();
+        assert_eq!(changes(), all);
+    }
+
+    #[test]
+    fn verify_and_rebuild_indexes() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(TreePlugin::<Hierarchy>::default().with_verification());
+        builder.finish();
+        let [a, a1, a2, a11, _a12] = add_small_tree(&app);
+        app.update();
+        assert_eq!(
+            app.run(|discrepancies: UniqueView<IndexDiscrepancies>| discrepancies.to_vec()),
+            vec![]
+        );
+
+        // break the indexes behind tree_indexing's back
+        app.run(
+            |mut vm_parent_index: ViewMut<ParentIndex>,
+             mut vm_sibling_index: ViewMut<SiblingIndex>| {
+                (&mut vm_parent_index).get(a).unwrap().children.pop_first();
+                vm_sibling_index.delete(a11);
+            },
+        );
+        let discrepancies = app.run(verify_tree_indexes::<Hierarchy>);
+        assert!(discrepancies.contains(&IndexDiscrepancy::MissingSiblingIndex { child: a11 }));
+        assert!(discrepancies.iter().any(|discrepancy| matches!(
+            discrepancy,
+            IndexDiscrepancy::WrongChildren { parent, .. } if *parent == a
+        )));
+
+        app.run(tree_index_rebuilding::<Hierarchy>);
+        assert_eq!(app.run(verify_tree_indexes::<Hierarchy>), vec![]);
+        app.run(|tree: TreeView, v_depth: View<Depth>| {
+            assert_eq!(tree.children(a).collect::<Vec<_>>(), vec![a1, a2]);
+            assert_eq!(tree.parent(a11), Some(a1));
+            assert_eq!(v_depth.get(a11).ok(), Some(&Depth::new(2)));
+        });
+    }
+
+    #[test]
+    fn verify_children_of_deleted_parent() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(TreePlugin::<Hierarchy>::default().with_verification());
+        builder.finish();
+        let [a, a1, a2, a11, a12] = add_small_tree(&app);
+
+        app.run(|mut all_storages: AllStoragesViewMut| {
+            all_storages.delete_entity(a1);
+        });
+        app.update();
+
+        let orphans = || {
+            app.run(|tree: TreeView, v_depth: View<Depth>| {
+                assert_eq!(tree.children(a).collect::<Vec<_>>(), vec![a2]);
+                assert_eq!(tree.parent(a11), Some(a1));
+                assert_eq!(tree.root_of(a12), a1);
+                (
+                    v_depth.get(a11).ok().copied(),
+                    v_depth.get(a12).ok().copied(),
+                )
+            })
+        };
+        assert_eq!(
+            app.run(|discrepancies: UniqueView<IndexDiscrepancies>| discrepancies.to_vec()),
+            vec![]
+        );
+        assert_eq!(orphans(), (Some(Depth::new(1)), Some(Depth::new(1))));
+
+        // rebuilding keeps the orphans linked like tree_indexing does
+        app.run(tree_index_rebuilding::<Hierarchy>);
+        assert_eq!(app.run(verify_tree_indexes::<Hierarchy>), vec![]);
+        assert_eq!(orphans(), (Some(Depth::new(1)), Some(Depth::new(1))));
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Component)]
+    struct Title(&'static str);
+
+    #[test]
+    fn nested_round_trip() {
+        let app = setup_app_with_plugin();
+        let ids = add_small_tree(&app);
+        let titles = ["a", "a1", "a2", "a11", "a12"];
+        app.run(|entities: EntitiesView, mut vm_title: ViewMut<Title>| {
+            for (id, title) in ids.iter().zip(titles.iter()) {
+                entities.add_component(*id, &mut vm_title, Title(title));
+            }
+        });
+
+        let export = |root: EntityId| {
+            app.run(|tree: TreeView, v_title: View<Title>| {
+                export_nested(&tree, root, &mut |id| v_title.get(id).unwrap().0)
+            })
+        };
+        let nested = export(ids[0]);
+        let leaf = |payload| NestedNode {
+            payload,
+            children: vec![],
+        };
+        assert_eq!(
+            nested,
+            NestedNode {
+                payload: "a",
+                children: vec![
+                    NestedNode {
+                        payload: "a1",
+                        children: vec![leaf("a11"), leaf("a12")],
+                    },
+                    leaf("a2"),
+                ],
+            }
+        );
+
+        let imported = app.run(
+            |mut entities: EntitiesViewMut,
+             mut vm_child_of: ViewMut<ChildOf>,
+             mut vm_title: ViewMut<Title>| {
+                import_nested(
+                    &mut entities,
+                    &mut vm_child_of,
+                    nested.clone(),
+                    &mut |entities, title| entities.add_entity(&mut vm_title, Title(title)),
+                )
+            },
+        );
+        app.update();
+
+        assert_ne!(imported, ids[0]);
+        assert_eq!(export(imported), nested);
+    }
+
+    #[test]
+    fn diff_and_patch_trees() {
+        let app = setup_app_with_plugin();
+        let replica = setup_app_with_plugin();
+        let [a, a1, a2, a11, a12] = add_small_tree(&app);
+        assert_eq!(add_small_tree(&replica), [a, a1, a2, a11, a12]);
+        let b = app.run(|mut entities: EntitiesViewMut| entities.add_entity((), ()));
+        replica.run(|mut entities: EntitiesViewMut| entities.add_entity((), ()));
+        let before = app.run(snapshot_tree::<Hierarchy>);
+
+        apply_moves(
+            &app,
+            vec![
+                MoveCmd {
+                    target: a2,
+                    place: MoveToPlace::FirstChildOf(a),
+                },
+                MoveCmd {
+                    target: a12,
+                    place: MoveToPlace::LastChildOf(a2),
+                },
+                MoveCmd {
+                    target: a11,
+                    place: MoveToPlace::Unlink,
+                },
+                MoveCmd {
+                    target: b,
+                    place: MoveToPlace::After(a12),
+                },
+            ],
+        );
+        let after = app.run(snapshot_tree::<Hierarchy>);
+
+        let patch = diff_trees(&before, &after);
+        assert_eq!(
+            patch,
+            vec![
+                TreePatch::Delete(a11),
+                // a1 stays, as moving a2 is enough
+                TreePatch::Reorder(MoveCmd {
+                    target: a2,
+                    place: MoveToPlace::FirstChildOf(a),
+                }),
+                TreePatch::Move(MoveCmd {
+                    target: a12,
+                    place: MoveToPlace::FirstChildOf(a2),
+                }),
+                TreePatch::Insert(MoveCmd {
+                    target: b,
+                    place: MoveToPlace::After(a12),
+                }),
+            ]
+        );
+
+        // replay on the replica
+        replica.run(|mut commands: UniqueViewMut<MoveCommands>| commands.push_patch(&patch));
+        replica.update();
+        let replayed = replica.run(snapshot_tree::<Hierarchy>);
+        assert_eq!(replayed.children(), after.children());
+        assert_eq!(diff_trees(&replayed, &after), vec![]);
+
+        // and undo on the original
+        let undo = diff_trees(&after, &before);
+        app.run(|mut commands: UniqueViewMut<MoveCommands>| commands.push_patch(&undo));
+        app.update();
+        assert_eq!(
+            app.run(snapshot_tree::<Hierarchy>).children(),
+            before.children()
+        );
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    struct Layout;
+
+    impl Relation for Layout {}
+
+    #[test]
+    fn independent_relation_trees() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        builder.add_plugin(TreePlugin::<Hierarchy>::default());
+        builder.add_plugin(TreePlugin::<Layout>::new());
+        builder.finish();
+        let [a, a1, a2, a11, _a12] = add_small_tree(&app);
+
+        // layout: a2 holds a and a11
+        app.run(
+            |entities: EntitiesView, mut vm_layout_child_of: ViewMut<ChildOf<Layout>>| {
+                entities.add_component(a, &mut vm_layout_child_of, ChildOf::new(a2, 1));
+                entities.add_component(a11, &mut vm_layout_child_of, ChildOf::new(a2, 2));
+            },
+        );
+        app.update();
+        app.run(|outline: TreeView, layout: TreeView<Layout>| {
+            assert_eq!(outline.children(a).collect::<Vec<_>>(), vec![a1, a2]);
+            assert_eq!(outline.parent(a11), Some(a1));
+            assert_eq!(layout.children(a2).collect::<Vec<_>>(), vec![a, a11]);
+            assert_eq!(layout.parent(a11), Some(a2));
+            assert_eq!(layout.children(a).count(), 0);
+        });
+
+        app.run(|mut commands: UniqueViewMut<MoveCommands<Layout>>| {
+            commands.push(MoveCmd {
+                target: a1,
+                place: MoveToPlace::LastChildOf(a11),
+            })
+        });
+        app.update();
+        app.run(
+            |outline: TreeView,
+             layout: TreeView<Layout>,
+             v_depth: View<Depth>,
+             v_layout_depth: View<Depth<Layout>>| {
+                assert_eq!(layout.path_from_root(a1), vec![a2, a11, a1]);
+                assert_eq!(v_layout_depth.get(a1).ok(), Some(&Depth::new(2)));
+                assert_eq!(outline.path_from_root(a1), vec![a, a1]);
+                assert_eq!(v_depth.get(a1).ok(), Some(&Depth::new(1)));
+            },
+        );
+        assert_eq!(app.run(verify_tree_indexes::<Hierarchy>), vec![]);
+        assert_eq!(app.run(verify_tree_indexes::<Layout>), vec![]);
+    }
+
+    #[test]
+    fn inherit_along_another_relation() {
+        let app = App::new();
+        let mut builder = AppBuilder::new(&app);
+        // brings in TreePlugin::<Layout> as a dependency
+        builder.add_plugin(InheritedPlugin::<Visible, Layout>::default());
+        builder.finish();
+
+        let (b, b1, b11) = app.run(
+            |mut entities: EntitiesViewMut,
+             mut vm_layout_child_of: ViewMut<ChildOf<Layout>>,
+             mut vm_visible: ViewMut<Visible>| {
+                let b = entities.add_entity(&mut vm_visible, Visible(false));
+                let b1 = entities.add_entity(&mut vm_layout_child_of, ChildOf::new(b, 1));
+                let b11 = entities.add_entity(&mut vm_layout_child_of, ChildOf::new(b1, 1));
+                (b, b1, b11)
+            },
+        );
+        app.update();
+
+        app.run(
+            |v_layout_inherited: View<Inherited<Visible, Layout>>,
+             v_inherited: View<Inherited<Visible>>| {
+                for id in [b, b1, b11].iter() {
+                    assert_eq!(
+                        v_layout_inherited
+                            .get(*id)
+                            .ok()
+                            .map(|inherited| inherited.0),
+                        Some(false)
+                    );
+                    assert!(!v_inherited.contains(*id));
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn invalid_moves_are_reported() {
+        let app = setup_app_with_plugin();
+        let (a, a1, a1a, b, dead) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a = entities.add_entity((), ());
+                let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                let a1a = entities.add_entity(&mut vm_child_of, ChildOf::new(a1, 1));
+                let b = entities.add_entity((), ());
+                let dead = entities.add_entity((), ());
+                (a, a1, a1a, b, dead)
+            },
+        );
+        app.run(|mut all_storages: AllStoragesViewMut| {
+            all_storages.delete_entity(dead);
+        });
+        app.update();
+
+        let into_descendant = MoveCmd {
+            target: a1,
+            place: MoveToPlace::LastChildOf(a1a),
+        };
+        let into_itself = MoveCmd {
+            target: a,
+            place: MoveToPlace::FirstChildOf(a),
+        };
+        let after_root = MoveCmd {
+            target: a1a,
+            place: MoveToPlace::After(b),
+        };
+        let into_dead = MoveCmd {
+            target: a1a,
+            place: MoveToPlace::LastChildOf(dead),
+        };
+        let errors = apply_moves(
+            &app,
+            vec![
+                into_descendant.clone(),
+                into_itself.clone(),
+                after_root.clone(),
+                into_dead.clone(),
+            ],
+        );
+
+        assert_eq!(
+            errors,
+            vec![
+                MoveError::CreatesCycle {
+                    cmd: into_descendant,
+                    parent: a1a,
+                },
+                MoveError::CreatesCycle {
+                    cmd: into_itself,
+                    parent: a,
+                },
+                MoveError::UnindexedSibling {
+                    cmd: after_root,
+                    sibling: b,
+                },
+                MoveError::DeadEntity {
+                    cmd: into_dead,
+                    entity: dead,
+                },
+            ]
+        );
+
+        // the tree is left as it was
+        app.run(|v_parent_index: View<ParentIndex>| {
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a).expect("has children")),
+                vec![a1]
+            );
+            assert_eq!(
+                parent_children_ids(v_parent_index.get(a1).expect("has children")),
+                vec![a1a]
+            );
+        });
+    }
+
+    #[test]
+    fn unlink_removes_child_of() {
+        let app = setup_app_with_plugin();
+        let (a, a1) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a = entities.add_entity((), ());
+                let a1 = entities.add_entity(&mut vm_child_of, ChildOf::new(a, 1));
+                (a, a1)
+            },
+        );
+        app.update();
+
+        let errors = apply_moves(
+            &app,
+            vec![MoveCmd {
+                target: a1,
+                place: MoveToPlace::Unlink,
+            }],
+        );
+
+        assert_eq!(errors, vec![]);
+        app.run(
+            |v_child_of: View<ChildOf>,
+             v_parent_index: View<ParentIndex>,
+             v_sibling_index: View<SiblingIndex>| {
+                assert_eq!(v_child_of.contains(a1), false);
+                assert_eq!(v_sibling_index.contains(a1), false);
+                assert_eq!(
+                    parent_children_ids(v_parent_index.get(a).expect("has a parent index")),
+                    vec![]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn large_fan_out_stays_indexed() {
+        let app = setup_app_with_plugin();
+        let (a, children) = app.run(
+            |mut entities: EntitiesViewMut, mut vm_child_of: ViewMut<ChildOf>| {
+                let a = entities.add_entity((), ());
+                // scattered and colliding hints
+                let children = (0..2_000)
+                    .map(|idx| {
+                        entities.add_entity(&mut vm_child_of, ChildOf::new(a, (idx * 7) as u8))
+                    })
+                    .collect::<Vec<_>>();
+                (a, children)
+            },
+        );
+        app.update();
+
+        let errors = apply_moves(
+            &app,
+            (0..50)
+                .map(|idx| MoveCmd {
+                    target: children[idx],
+                    place: MoveToPlace::After(children[children.len() - 1 - idx]),
+                })
+                .collect(),
+        );
+        assert_eq!(errors, vec![]);
+        app.run(|mut all_storages: AllStoragesViewMut| {
+            for child in children.iter().skip(100).step_by(10) {
+                all_storages.delete_entity(*child);
+            }
+        });
+        app.update();
+
+        assert_eq!(app.run(verify_tree_indexes::<Hierarchy>), vec![]);
+        app.run(|tree: TreeView| assert_eq!(tree.children(a).count(), 2_000 - 190));
+    }
+
+    fn parent_children_ids(pi: &ParentIndex) -> Vec<EntityId> {
+        pi.children.iter().map(|c| c.1).collect()
+    }
+}
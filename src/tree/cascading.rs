@@ -0,0 +1,193 @@
+use super::*;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use tracing::*;
+
+/// What happens to the children of a node when the node's entity or its [ChildOf] is deleted, see [TreePlugin::with_delete_policy].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeletePolicy {
+    /// Children keep their [ChildOf] pointing at the deleted node
+    Keep,
+    /// Delete the entities of all descendants of the node
+    DeleteDescendants,
+    /// Move the children in place of the node under its former parent, or detach them if it had none
+    ReparentToGrandparent,
+    /// Delete the [ChildOf] of the children, making them roots of their own trees
+    Detach,
+}
+
+impl Default for DeletePolicy {
+    fn default() -> Self {
+        DeletePolicy::Keep
+    }
+}
+
+/// The [DeletePolicy] of the tree of relation `R`, added as a unique by [TreePlugin]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreeDeletePolicy<R = Hierarchy>(pub DeletePolicy, pub PhantomData<R>);
+
+impl<R: Relation> Component for TreeDeletePolicy<R> {
+    type Tracking = track::Untracked;
+}
+
+impl<R: Relation> TreeDeletePolicy<R> {
+    pub fn new(policy: DeletePolicy) -> Self {
+        TreeDeletePolicy(policy, PhantomData)
+    }
+}
+
+/// Applies the [TreeDeletePolicy] unique to the children of deleted and unlinked nodes, before [tree_indexing] indexes the changes.
+///
+/// Deleted nodes are found through the [ParentIndex] deleted along with their entity, unlinked nodes through their
+/// deleted [ChildOf], so only nodes with indexed children cascade. An unlinked node stays alive as a root, only its
+/// descendants are deleted or moved. Moves are not deletions: [MoveToPlace::Unlink] and [MoveToPlace::Swap] with a root,
+/// found in [MoveCommands::applied], keep the subtree of the node becoming a root.
+pub fn tree_cascading<R: Relation>(mut all_storages: AllStoragesViewMut) {
+    let policy = all_storages
+        .borrow::<UniqueView<TreeDeletePolicy<R>>>()
+        .expect("TreePlugin adds the TreeDeletePolicy unique")
+        .0;
+    if policy == DeletePolicy::Keep {
+        return;
+    }
+
+    let to_delete = all_storages
+        .run(
+            |v_entities: EntitiesView,
+             commands: UniqueView<MoveCommands<R>>,
+             mut vm_child_of: ViewMut<ChildOf<R>>,
+             mut vm_sibling_index: ViewMut<SiblingIndex<R>>,
+             mut vm_parent_index: ViewMut<ParentIndex<R>>| {
+                // children deleted or moved elsewhere since they were indexed are left alone
+                let orphans_of = |removed_id: EntityId, parent_index: &ParentIndex<R>| {
+                    parent_index
+                        .children
+                        .iter()
+                        .map(|(_, child)| *child)
+                        .filter(|child| {
+                            (&vm_child_of)
+                                .get(*child)
+                                .map_or(false, |ChildOf(parent_id, ..)| *parent_id == removed_id)
+                        })
+                        .collect::<Vec<_>>()
+                };
+                // the ParentIndex of a live entity is only deleted when rebuilding the indexes
+                let mut removed = vm_parent_index
+                    .deleted()
+                    .iter()
+                    .filter(|(id, _)| !v_entities.is_alive(*id))
+                    .map(|(id, parent_index)| (*id, orphans_of(*id, parent_index)))
+                    .collect::<Vec<_>>();
+                let moved_to_root = commands
+                    .applied()
+                    .iter()
+                    .filter(|(_, child_of)| child_of.is_none())
+                    .map(|(id, _)| *id)
+                    .collect::<HashSet<_>>();
+                removed.extend(
+                    vm_child_of
+                        .deleted()
+                        .iter()
+                        .map(|(id, _)| *id)
+                        .filter(|id| {
+                            v_entities.is_alive(*id)
+                                && !vm_child_of.contains(*id)
+                                && !moved_to_root.contains(id)
+                        })
+                        .filter_map(|id| {
+                            let parent_index = (&vm_parent_index).get(id).ok()?;
+                            Some((id, orphans_of(id, parent_index)))
+                        }),
+                );
+                removed.sort_by_key(|(id, _)| *id);
+                removed.dedup_by_key(|(id, _)| *id);
+                // the ChildOf deleted along with or from the removed nodes, if they had a parent
+                let removed_child_of = vm_child_of
+                    .deleted()
+                    .iter()
+                    .map(|(id, child_of)| (*id, child_of.clone()))
+                    .collect::<HashMap<_, _>>();
+
+                let mut to_delete = Vec::new();
+                for (removed_id, orphans) in removed {
+                    if orphans.is_empty() {
+                        continue;
+                    }
+                    trace!(
+                        ?removed_id,
+                        ?policy,
+                        orphans = orphans.len(),
+                        "cascading delete"
+                    );
+
+                    let grandparent = removed_child_of
+                        .get(&removed_id)
+                        .filter(|ChildOf(grandparent_id, ..)| v_entities.is_alive(*grandparent_id));
+                    match (policy, grandparent) {
+                        (DeletePolicy::Keep, _) => {}
+                        (DeletePolicy::DeleteDescendants, _) => {
+                            let mut stack = orphans;
+                            let mut descendants = Vec::new();
+                            // a ChildOf cycle below the removed node would otherwise be walked forever
+                            let mut visited = HashSet::new();
+                            while let Some(descendant) = stack.pop() {
+                                if !visited.insert(descendant) {
+                                    continue;
+                                }
+                                descendants.push(descendant);
+                                if let Ok(parent_index) = (&vm_parent_index).get(descendant) {
+                                    stack.extend(
+                                        parent_index.children.iter().map(|(_, child)| *child),
+                                    );
+                                }
+                            }
+                            // deepest first, so every unlink finds its parent still indexed
+                            for descendant in descendants.iter().rev() {
+                                indexing::unlink_child(
+                                    &mut vm_sibling_index,
+                                    &mut vm_parent_index,
+                                    *descendant,
+                                );
+                            }
+                            to_delete.extend(descendants);
+                        }
+                        (
+                            DeletePolicy::ReparentToGrandparent,
+                            Some(ChildOf(grandparent_id, removed_order, _)),
+                        ) => {
+                            // between the removed node's place and its next sibling, which it is still indexed with
+                            let upper = (&vm_parent_index)
+                                .get(*grandparent_id)
+                                .ok()
+                                .and_then(|parent_index| {
+                                    parent_index
+                                        .children
+                                        .iter()
+                                        .map(|(ordered, _)| ordered)
+                                        .find(|ordered| *ordered > removed_order)
+                                        .cloned()
+                                })
+                                .unwrap_or_else(|| removed_order.after());
+                            let keys = OrderedRange::new(removed_order, &upper)
+                                .evenly_spaced_between(orphans.len());
+                            for (orphan, key) in orphans.iter().zip(keys) {
+                                *(&mut vm_child_of).get(*orphan).unwrap() =
+                                    ChildOf::ordered(*grandparent_id, key);
+                            }
+                        }
+                        (DeletePolicy::ReparentToGrandparent, None) | (DeletePolicy::Detach, _) => {
+                            for orphan in orphans.iter() {
+                                vm_child_of.delete(*orphan);
+                            }
+                        }
+                    }
+                }
+                to_delete
+            },
+        )
+        .expect("all mine!");
+
+    for id in to_delete {
+        all_storages.delete_entity(id);
+    }
+}
@@ -0,0 +1,174 @@
+use super::*;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+/// The [ChildOf] of every linked entity at one point in time, see [snapshot_tree] and [diff_trees]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TreeSnapshot<R = Hierarchy>(pub BTreeMap<EntityId, ChildOf<R>>);
+
+impl<R: Relation> TreeSnapshot<R> {
+    /// Children of each parent, in order
+    pub fn children(&self) -> BTreeMap<EntityId, Vec<EntityId>> {
+        let mut children: BTreeMap<EntityId, Vec<(&Ordered, EntityId)>> = BTreeMap::new();
+        for (id, ChildOf(parent_id, ordered, _)) in self.0.iter() {
+            children.entry(*parent_id).or_default().push((ordered, *id));
+        }
+        children
+            .into_iter()
+            .map(|(parent_id, mut siblings)| {
+                siblings.sort();
+                (parent_id, siblings.into_iter().map(|(_, id)| id).collect())
+            })
+            .collect()
+    }
+}
+
+/// Take a [TreeSnapshot], e.g. with `app.run(snapshot_tree::<R>)`
+pub fn snapshot_tree<R: Relation>(v_child_of: View<ChildOf<R>>) -> TreeSnapshot<R> {
+    TreeSnapshot(
+        v_child_of
+            .iter()
+            .with_id()
+            .map(|(id, child_of)| (id, child_of.clone()))
+            .collect(),
+    )
+}
+
+/// One structural edit of a tree, see [diff_trees]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreePatch {
+    /// Link an entity which had no parent
+    Insert(MoveCmd),
+    /// Unlink an entity which had a parent
+    Delete(EntityId),
+    /// Move an entity to another parent
+    Move(MoveCmd),
+    /// Move an entity among its siblings
+    Reorder(MoveCmd),
+}
+
+impl TreePatch {
+    pub fn to_move_cmd(&self) -> MoveCmd {
+        match self {
+            TreePatch::Insert(cmd) | TreePatch::Move(cmd) | TreePatch::Reorder(cmd) => cmd.clone(),
+            TreePatch::Delete(target) => MoveCmd {
+                target: *target,
+                place: MoveToPlace::Unlink,
+            },
+        }
+    }
+}
+
+impl<R: Relation> MoveCommands<R> {
+    /// Queue the moves of `patch` for [tree_reordering], in order
+    pub fn push_patch(&mut self, patch: &[TreePatch]) {
+        self.extend(patch.iter().map(TreePatch::to_move_cmd));
+    }
+}
+
+/// Edits turning `before` into `after`, placing entities relative to their new siblings.
+///
+/// Children which keep their parent are only reordered if they are not part of the longest run of children
+/// keeping their relative order. Unlinks come first, then parents are placed before their children,
+/// so replaying the patch through [tree_reordering] never creates a cycle.
+pub fn diff_trees<R: Relation>(
+    before: &TreeSnapshot<R>,
+    after: &TreeSnapshot<R>,
+) -> Vec<TreePatch> {
+    let mut patch = before
+        .0
+        .keys()
+        .filter(|id| !after.0.contains_key(id))
+        .map(|id| TreePatch::Delete(*id))
+        .collect::<Vec<_>>();
+
+    let before_children = before.children();
+    let after_children = after.children();
+
+    // top down from the roots of after
+    let mut queue = after_children
+        .keys()
+        .filter(|parent_id| !after.0.contains_key(parent_id))
+        .copied()
+        .collect::<VecDeque<_>>();
+    let mut visited = HashSet::new();
+    while let Some(parent_id) = queue.pop_front() {
+        if !visited.insert(parent_id) {
+            continue;
+        }
+        let children = match after_children.get(&parent_id) {
+            Some(children) => children,
+            None => continue,
+        };
+
+        let previous_order = before_children.get(&parent_id).map_or(&[][..], |c| &c[..]);
+        let kept = children
+            .iter()
+            .filter_map(|child| previous_order.iter().position(|id| id == child))
+            .collect::<Vec<_>>();
+        let unmoved = longest_increasing(&kept)
+            .into_iter()
+            .map(|idx| previous_order[kept[idx]])
+            .collect::<HashSet<_>>();
+
+        for (idx, child) in children.iter().enumerate() {
+            queue.push_back(*child);
+            if unmoved.contains(child) {
+                continue;
+            }
+            let cmd = MoveCmd {
+                target: *child,
+                place: match idx.checked_sub(1) {
+                    Some(prev) => MoveToPlace::After(children[prev]),
+                    None => MoveToPlace::FirstChildOf(parent_id),
+                },
+            };
+            patch.push(match before.0.get(child) {
+                None => TreePatch::Insert(cmd),
+                Some(ChildOf(previous_parent, ..)) if *previous_parent != parent_id => {
+                    TreePatch::Move(cmd)
+                }
+                Some(_) => TreePatch::Reorder(cmd),
+            });
+        }
+    }
+
+    patch
+}
+
+/// Indexes into `values` of a longest strictly increasing subsequence
+fn longest_increasing(values: &[usize]) -> Vec<usize> {
+    // tails[len] is the index of the smallest value ending an increasing run of len + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev = vec![None; values.len()];
+    for (idx, value) in values.iter().enumerate() {
+        let len = tails.partition_point(|tail| values[*tail] < *value);
+        prev[idx] = len.checked_sub(1).map(|before| tails[before]);
+        if len == tails.len() {
+            tails.push(idx);
+        } else {
+            tails[len] = idx;
+        }
+    }
+
+    let mut run = Vec::with_capacity(tails.len());
+    let mut current = tails.last().copied();
+    while let Some(idx) = current {
+        run.push(idx);
+        current = prev[idx];
+    }
+    run.reverse();
+    run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_increasing_run() {
+        assert_eq!(longest_increasing(&[]), Vec::<usize>::new());
+        assert_eq!(longest_increasing(&[0, 1, 2]), vec![0, 1, 2]);
+        // 1, 2, 4
+        assert_eq!(longest_increasing(&[3, 1, 2, 0, 4]), vec![1, 2, 4]);
+    }
+}
@@ -0,0 +1,510 @@
+use super::*;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::marker::PhantomData;
+use std::ops::Bound;
+use tracing::*;
+
+/// Siblings are given new, evenly spaced keys once one of their keys grows longer than this (in bytes),
+/// so repeatedly moving entities to the same spot does not keep growing keys.
+pub const REBALANCE_KEY_LEN: usize = 16;
+
+// Ordered first in tuple so it takes ordering precedence
+type SiblingID = (Ordered, EntityId);
+
+/// Managed by the tree_indexing system to provide more concise info for walking the tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SiblingIndex<R = Hierarchy> {
+    pub parent_node: EntityId,
+    pub ordered_node: SiblingID,
+    pub prev_sibling: Option<SiblingID>,
+    pub next_sibling: Option<SiblingID>,
+    pub(super) relation: PhantomData<R>,
+}
+
+impl<R: Relation> Component for SiblingIndex<R> {
+    type Tracking = track::Untracked;
+}
+
+/// Managed by the tree_indexing system to provide more concise info for walking the tree
+///
+/// Children are kept ordered in a [BTreeSet], so linking and unlinking a child is O(log n) even for parents with many children.
+/// Tracked so [tree_cascading](super::tree_cascading) finds the children of deleted parents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParentIndex<R = Hierarchy> {
+    pub children: BTreeSet<SiblingID>,
+    pub(super) relation: PhantomData<R>,
+}
+
+impl<R: Relation> Component for ParentIndex<R> {
+    type Tracking = track::All;
+}
+
+impl<R: Relation> ParentIndex<R> {
+    pub(super) fn new(children: BTreeSet<SiblingID>) -> Self {
+        ParentIndex {
+            children,
+            relation: PhantomData,
+        }
+    }
+}
+
+/// Number of ancestors of an indexed node, 0 for roots.
+///
+/// Maintained by the tree_indexing system for every entity with a [SiblingIndex] or [ParentIndex]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Depth<R = Hierarchy>(pub usize, pub PhantomData<R>);
+
+impl<R: Relation> Component for Depth<R> {
+    type Tracking = track::Untracked;
+}
+
+impl<R: Relation> Depth<R> {
+    pub fn new(depth: usize) -> Self {
+        Depth(depth, PhantomData)
+    }
+}
+
+/// Topmost ancestor of an indexed node, the node itself for roots.
+///
+/// Maintained by the tree_indexing system for every entity with a [SiblingIndex] or [ParentIndex]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreeRoot<R = Hierarchy>(pub EntityId, pub PhantomData<R>);
+
+impl<R: Relation> Component for TreeRoot<R> {
+    type Tracking = track::Untracked;
+}
+
+impl<R: Relation> TreeRoot<R> {
+    pub fn new(root: EntityId) -> Self {
+        TreeRoot(root, PhantomData)
+    }
+}
+
+/// Marks the roots of subtrees which were moved, linked or unlinked by the last run of the tree_indexing system,
+/// so values derived from the structure (like [Inherited]) can be updated for those subtrees only.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reindexed<R = Hierarchy>(pub PhantomData<R>);
+
+impl<R: Relation> Component for Reindexed<R> {
+    type Tracking = track::Untracked;
+}
+
+/// Indexes tree [ChildOf] and [Ordering] components into more helpful between nodes
+///
+/// Children of parents with equal or overly long keys (see [REBALANCE_KEY_LEN]) are rebalanced,
+/// leaving their new [ChildOf] values marked as modified for the next update.
+///
+/// [Depth] and [TreeRoot] are only recomputed for the subtrees of changed [ChildOf]s.
+/// Each [Relation] is indexed by its own `tree_indexing::<R>`.
+pub fn tree_indexing<R: Relation>(
+    (
+        v_entities,
+        mut vm_child_of,
+        mut vm_sibling_index,
+        mut vm_parent_index,
+        mut vm_depth,
+        mut vm_tree_root,
+        mut vm_reindexed,
+    ): (
+        EntitiesView,
+        ViewMut<ChildOf<R>>,
+        ViewMut<SiblingIndex<R>>,
+        ViewMut<ParentIndex<R>>,
+        ViewMut<Depth<R>>,
+        ViewMut<TreeRoot<R>>,
+        ViewMut<Reindexed<R>>,
+    ),
+) {
+    vm_reindexed.clear();
+
+    // nodes whose subtree may have a new depth or root
+    let mut moved = Vec::new();
+
+    // children of deleted parents stay indexed as siblings, under a parent which is no longer alive
+    moved.extend(
+        vm_parent_index
+            .deleted()
+            .iter()
+            .filter(|(id, _)| !v_entities.is_alive(*id))
+            .flat_map(|(_, parent_index)| parent_index.children.iter().map(|(_, child)| *child)),
+    );
+
+    // iff ChildOf was completely deleted (does not include "removed")
+    vm_child_of.take_deleted().into_iter().for_each(
+        |(deleted_id, ChildOf(parent_id, ordered, _))| {
+            moved.push(deleted_id);
+            if vm_sibling_index.contains(deleted_id) {
+                unlink_child(&mut vm_sibling_index, &mut vm_parent_index, deleted_id);
+            } else {
+                // the whole entity was deleted, along with its SiblingIndex
+                unlink_deleted_child(
+                    &mut vm_sibling_index,
+                    &mut vm_parent_index,
+                    deleted_id,
+                    &ordered,
+                    parent_id,
+                );
+            }
+        },
+    );
+
+    // parents which had children inserted or moved
+    let mut touched_parents = HashSet::new();
+    // parents whose children were given equal or overly long keys
+    let mut unbalanced_parents = HashSet::new();
+
+    // iff ChildOf is completely new component
+    vm_child_of.inserted().iter().with_id().for_each(
+        |(inserted_id, ChildOf(parent_id, child_order, _))| {
+            touched_parents.insert(*parent_id);
+            moved.push(inserted_id);
+            if insert_child_of(
+                &v_entities,
+                &mut vm_sibling_index,
+                &mut vm_parent_index,
+                inserted_id,
+                &child_order,
+                *parent_id,
+            ) {
+                unbalanced_parents.insert(*parent_id);
+            }
+        },
+    );
+
+    // iff ChildOf was modified
+    vm_child_of.modified().iter().with_id().for_each(
+        |(modified_id, ChildOf(parent_id, child_order, _))| {
+            touched_parents.insert(*parent_id);
+            moved.push(modified_id);
+
+            // reinsert child, removing it from its previous parent
+            if insert_child_of(
+                &v_entities,
+                &mut vm_sibling_index,
+                &mut vm_parent_index,
+                modified_id,
+                &child_order,
+                *parent_id,
+            ) {
+                unbalanced_parents.insert(*parent_id);
+            }
+        },
+    );
+
+    vm_child_of.clear_all_inserted_and_modified();
+
+    // after clearing, so the rebalanced ChildOf values are seen as changes by the next update
+    for parent_id in unbalanced_parents {
+        rebalance_children(
+            &mut vm_child_of,
+            &mut vm_sibling_index,
+            &mut vm_parent_index,
+            parent_id,
+        );
+    }
+
+    // parents which just became roots have no depth yet
+    moved.extend(
+        touched_parents
+            .into_iter()
+            .filter(|parent_id| !vm_depth.contains(*parent_id)),
+    );
+    let mut refreshed = HashSet::new();
+    for id in moved {
+        if !v_entities.is_alive(id) {
+            continue;
+        }
+        v_entities.add_component(id, &mut vm_reindexed, Reindexed::default());
+        if !refreshed.contains(&id) {
+            refresh_depth_and_root(
+                &v_entities,
+                &vm_sibling_index,
+                &vm_parent_index,
+                &mut vm_depth,
+                &mut vm_tree_root,
+                &mut refreshed,
+                id,
+            );
+        }
+    }
+}
+
+/// Set [Depth] and [TreeRoot] of `id` and its descendants from the indexes, skipping nodes already in `refreshed`.
+pub(super) fn refresh_depth_and_root<R: Relation>(
+    v_entities: &EntitiesView,
+    v_sibling_index: &ViewMut<SiblingIndex<R>>,
+    v_parent_index: &ViewMut<ParentIndex<R>>,
+    vm_depth: &mut ViewMut<Depth<R>>,
+    vm_tree_root: &mut ViewMut<TreeRoot<R>>,
+    refreshed: &mut HashSet<EntityId>,
+    id: EntityId,
+) {
+    if !v_sibling_index.contains(id) && !v_parent_index.contains(id) {
+        // no longer part of a tree
+        vm_depth.delete(id);
+        vm_tree_root.delete(id);
+        return;
+    }
+
+    let mut depth = 0;
+    let mut root = id;
+    let mut visited = HashSet::new();
+    while let Ok(sibling_index) = v_sibling_index.get(root) {
+        if !visited.insert(root) {
+            // ChildOf was set to form a cycle, without going through tree_reordering
+            break;
+        }
+        root = sibling_index.parent_node;
+        depth += 1;
+    }
+
+    let mut queue = VecDeque::new();
+    queue.push_back((id, depth));
+    while let Some((node, depth)) = queue.pop_front() {
+        if !refreshed.insert(node) {
+            continue;
+        }
+        v_entities.add_component(node, &mut *vm_depth, Depth::new(depth));
+        v_entities.add_component(node, &mut *vm_tree_root, TreeRoot::new(root));
+        if let Ok(parent_index) = v_parent_index.get(node) {
+            queue.extend(
+                parent_index
+                    .children
+                    .iter()
+                    .map(|(_, child)| (*child, depth + 1)),
+            );
+        }
+    }
+}
+
+/// Whether a child inserted with `key` between `prev_sibling` and `next_sibling` leaves its siblings to be rebalanced,
+/// the other keys were checked when they were inserted.
+fn needs_rebalance(
+    key: &Ordered,
+    prev_sibling: &Option<SiblingID>,
+    next_sibling: &Option<SiblingID>,
+) -> bool {
+    key.as_bytes().len() > REBALANCE_KEY_LEN
+        || prev_sibling
+            .iter()
+            .chain(next_sibling)
+            .any(|(ordered, _)| ordered == key)
+}
+
+/// Give the children of `parent_id` evenly spaced keys in their current order, updating [ChildOf] and the indexes.
+pub(super) fn rebalance_children<R: Relation>(
+    vm_child_of: &mut ViewMut<ChildOf<R>>,
+    vm_sibling_index: &mut ViewMut<SiblingIndex<R>>,
+    vm_parent_index: &mut ViewMut<ParentIndex<R>>,
+    parent_id: EntityId,
+) {
+    let mut parent_index = match vm_parent_index.get(parent_id) {
+        Ok(parent_index) => parent_index,
+        Err(_) => return,
+    };
+
+    let keys = OrderedRange::new(&Ordered::hinted(0), &Ordered::hinted(255))
+        .evenly_spaced_between(parent_index.children.len());
+    trace!(?parent_id, children = keys.len(), "rebalancing children");
+    let children = std::mem::take(&mut parent_index.children)
+        .into_iter()
+        .zip(keys)
+        .map(|((_, child_id), key)| {
+            if let Ok(mut child_of) = (&mut *vm_child_of).get(child_id) {
+                child_of.1 = key.clone();
+            }
+            (key, child_id)
+        })
+        .collect::<Vec<_>>();
+
+    for (idx, sibling_id) in children.iter().enumerate() {
+        if let Ok(sibling_index) = (&mut *vm_sibling_index).get(sibling_id.1) {
+            sibling_index.ordered_node = sibling_id.clone();
+            sibling_index.prev_sibling = idx.checked_sub(1).map(|prev| children[prev].clone());
+            sibling_index.next_sibling = children.get(idx + 1).cloned();
+        }
+    }
+    parent_index.children = children.into_iter().collect();
+}
+
+/// Index `child_id` among the children of `parent_id` at `child_order`, unlinking it from its previous place first.
+///
+/// The [ParentIndex] of a parent is created along with its first child, and kept up to date child by child
+/// in O(log n), so parents with many children never have to be rebuilt from all [ChildOf]s.
+/// Returns whether the children of `parent_id` need to be rebalanced, see [REBALANCE_KEY_LEN].
+pub(super) fn insert_child_of<R: Relation>(
+    v_entities: &EntitiesView,
+    vm_sibling_index: &mut ViewMut<SiblingIndex<R>>,
+    vm_parent_index: &mut ViewMut<ParentIndex<R>>,
+    child_id: EntityId,
+    child_order: &Ordered, // used to position between siblings
+    parent_id: EntityId,   // insert to this parent
+) -> bool {
+    let to_insert: SiblingID = (child_order.clone(), child_id);
+    match vm_sibling_index.get(child_id) {
+        // already in place, e.g. moved by tree_reordering
+        Ok(sibling_index)
+            if sibling_index.parent_node == parent_id
+                && sibling_index.ordered_node == to_insert =>
+        {
+            return needs_rebalance(
+                child_order,
+                &sibling_index.prev_sibling,
+                &sibling_index.next_sibling,
+            );
+        }
+        // the parent or just the Ordered changed
+        Ok(_) => unlink_child(vm_sibling_index, vm_parent_index, child_id),
+        Err(_) => {}
+    }
+
+    if !vm_parent_index.contains(parent_id) {
+        // parent has no parent or siblings
+        v_entities.add_component(
+            parent_id,
+            &mut *vm_parent_index,
+            ParentIndex::new(BTreeSet::new()),
+        );
+    }
+    let mut parent_index = vm_parent_index
+        .get(parent_id)
+        .expect("parent should have a parent index now");
+    let siblings = &mut parent_index.children;
+
+    let prev_node_opt = siblings.range(..&to_insert).next_back().cloned();
+    let next_node_opt = siblings
+        .range((Bound::Excluded(&to_insert), Bound::Unbounded))
+        .next()
+        .cloned();
+    siblings.insert(to_insert.clone());
+
+    // update references
+    if let Some(prev_node) = &prev_node_opt {
+        // prev node should point at inserted node as next
+        match vm_sibling_index.get(prev_node.1) {
+            Ok(prev_index) => prev_index.next_sibling = Some(to_insert.clone()),
+            Err(_) => warn!(
+                ?prev_node,
+                "previous sibling is not indexed, see verify_tree_indexes"
+            ),
+        }
+    }
+
+    if let Some(next_node) = &next_node_opt {
+        // next node should point at inserted node as prev
+        match vm_sibling_index.get(next_node.1) {
+            Ok(next_index) => next_index.prev_sibling = Some(to_insert.clone()),
+            Err(_) => warn!(
+                ?next_node,
+                "next sibling is not indexed, see verify_tree_indexes"
+            ),
+        }
+    }
+
+    let rebalance = needs_rebalance(child_order, &prev_node_opt, &next_node_opt);
+    v_entities.add_component(
+        child_id,
+        vm_sibling_index,
+        SiblingIndex {
+            ordered_node: to_insert,
+            next_sibling: next_node_opt,
+            prev_sibling: prev_node_opt,
+            parent_node: parent_id,
+            relation: PhantomData,
+        },
+    );
+    rebalance
+}
+
+pub(super) fn unlink_child<R: Relation>(
+    vm_sibling_index: &mut ViewMut<SiblingIndex<R>>,
+    vm_parent_index: &mut ViewMut<ParentIndex<R>>,
+    child: EntityId,
+) {
+    let (parent_id, t_ordered_node, t_prev_sibling, t_next_sibling) =
+        match vm_sibling_index.get(child) {
+            Ok(child_index) => (
+                child_index.parent_node,
+                child_index.ordered_node.clone(),
+                child_index.prev_sibling.clone(),
+                child_index.next_sibling.clone(),
+            ),
+            // never indexed (e.g. ChildOf added and removed before indexing), so nothing to unlink
+            Err(_) => return,
+        };
+
+    // parent: remove T from children
+    if let Ok(mut parent_index) = vm_parent_index.get(parent_id) {
+        parent_index.children.remove(&t_ordered_node);
+    }
+
+    relink_siblings(vm_sibling_index, &t_prev_sibling, &t_next_sibling);
+
+    vm_sibling_index.delete(child);
+}
+
+/// Point `prev_sibling` and `next_sibling` at each other, after unlinking the child between them.
+fn relink_siblings<R: Relation>(
+    vm_sibling_index: &mut ViewMut<SiblingIndex<R>>,
+    prev_sibling: &Option<SiblingID>,
+    next_sibling: &Option<SiblingID>,
+) {
+    if let Some(prev_sibling_id) = prev_sibling {
+        // prevsibling: set nextsibling to T's nextsibling
+        match vm_sibling_index.get(prev_sibling_id.1) {
+            Ok(prev_sibling_index) => prev_sibling_index.next_sibling = next_sibling.clone(),
+            Err(_) => warn!(
+                ?prev_sibling_id,
+                "previous sibling is not indexed, see verify_tree_indexes"
+            ),
+        }
+    }
+
+    if let Some(next_sibling_id) = next_sibling {
+        // nextsibling: set prevsibling to T's prevsibling
+        match vm_sibling_index.get(next_sibling_id.1) {
+            Ok(next_sibling_index) => next_sibling_index.prev_sibling = prev_sibling.clone(),
+            Err(_) => warn!(
+                ?next_sibling_id,
+                "next sibling is not indexed, see verify_tree_indexes"
+            ),
+        }
+    }
+}
+
+/// Unlink a child without a [SiblingIndex], finding its siblings through the [ParentIndex] of `parent_id` instead.
+///
+/// `ordered` is the last key of the deleted [ChildOf], which the child is indexed with unless it changed in the same update.
+fn unlink_deleted_child<R: Relation>(
+    vm_sibling_index: &mut ViewMut<SiblingIndex<R>>,
+    vm_parent_index: &mut ViewMut<ParentIndex<R>>,
+    child: EntityId,
+    ordered: &Ordered,
+    parent_id: EntityId,
+) {
+    let mut parent_index = match vm_parent_index.get(parent_id) {
+        Ok(parent_index) => parent_index,
+        Err(_) => return,
+    };
+    let mut sibling_id = (ordered.clone(), child);
+    if !parent_index.children.contains(&sibling_id) {
+        sibling_id = match parent_index.children.iter().find(|(_, id)| id == &child) {
+            Some(indexed) => indexed.clone(),
+            // already unlinked
+            None => return,
+        };
+    }
+    parent_index.children.remove(&sibling_id);
+
+    let prev_sibling = parent_index
+        .children
+        .range(..&sibling_id)
+        .next_back()
+        .cloned();
+    let next_sibling = parent_index
+        .children
+        .range((Bound::Excluded(&sibling_id), Bound::Unbounded))
+        .next()
+        .cloned();
+    relink_siblings(vm_sibling_index, &prev_sibling, &next_sibling);
+}
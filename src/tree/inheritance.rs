@@ -0,0 +1,160 @@
+use super::*;
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
+/// Effective value of `T` for a node of the tree: its own `T`, or the `T` of its nearest ancestor which has one.
+///
+/// Maintained by [tree_inheriting] for every node which has or inherits a `T` in the tree of relation `R`, see [InheritedPlugin].
+/// Tracked, so systems after [tree_inheriting] can react to the nodes whose effective value changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inherited<T, R = Hierarchy> {
+    value: T,
+    relation: PhantomData<R>,
+}
+
+impl<T: Send + Sync + 'static, R: Relation> Component for Inherited<T, R> {
+    type Tracking = track::All;
+}
+
+impl<T, R> Inherited<T, R> {
+    pub fn new(value: T) -> Self {
+        Inherited {
+            value,
+            relation: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, R> std::ops::Deref for Inherited<T, R> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T, R> std::ops::DerefMut for Inherited<T, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+/// A view to recompute [Inherited]`<T>` for the subtrees of nodes whose `T` changed, or which were [Reindexed].
+///
+/// Like [UpdateOneToOne](crate::UpdateOneToOne), values equal to the previous value are not written,
+/// and [Inherited]`<T>` is deleted from nodes which no longer have or inherit a `T`.
+pub struct UpdateInherited<'a, T: Component<Tracking = track::All>, R: Relation = Hierarchy>(
+    View<'a, T>,
+    View<'a, Reindexed<R>>,
+    TreeView<'a, R>,
+    ViewMut<'a, Inherited<T, R>>,
+);
+
+impl<T, R> UpdateInherited<'_, T, R>
+where
+    T: Clone + PartialEq + Send + Sync + Component<Tracking = track::All>,
+    R: Relation,
+{
+    pub fn update(self) {
+        let UpdateInherited(v_t, v_reindexed, tree, mut vm_inherited) = self;
+
+        let mut changed = v_t
+            .inserted_or_modified()
+            .iter()
+            .with_id()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        changed.extend(v_t.removed_or_deleted());
+        changed.extend(v_reindexed.iter().with_id().map(|(id, _)| id));
+
+        let mut refreshed = HashSet::new();
+        for id in changed {
+            if refreshed.contains(&id) {
+                continue;
+            }
+            let from_ancestors = tree
+                .ancestors(id)
+                .find_map(|ancestor| v_t.get(ancestor).ok())
+                .cloned();
+
+            let mut stack = vec![(id, from_ancestors)];
+            while let Some((node, inherited)) = stack.pop() {
+                if !refreshed.insert(node) {
+                    continue;
+                }
+                let effective = v_t.get(node).ok().cloned().or(inherited);
+                match &effective {
+                    Some(value) => {
+                        if let Ok(mut existing) = (&mut vm_inherited).get(node) {
+                            if existing.value != *value {
+                                existing.value = value.clone();
+                            }
+                        } else {
+                            vm_inherited
+                                .add_component_unchecked(node, Inherited::new(value.clone()));
+                        }
+                    }
+                    None => {
+                        vm_inherited.delete(node);
+                    }
+                }
+                stack.extend(tree.children(node).map(|child| (child, effective.clone())));
+            }
+        }
+    }
+}
+
+/// Recomputes [Inherited]`<T, R>` after [tree_indexing], see [UpdateInherited].
+pub fn tree_inheriting<T, R>(update_inherited: UpdateInherited<T, R>)
+where
+    T: Clone + PartialEq + Send + Sync + Component<Tracking = track::All>,
+    R: Relation,
+{
+    update_inherited.update();
+}
+
+pub struct UpdateInheritedBorrower<T, R>(T, R);
+
+impl<T, R> IntoBorrow for UpdateInherited<'_, T, R>
+where
+    T: Send + Sync + Component<Tracking = track::All>,
+    R: Relation,
+{
+    type Borrow = UpdateInheritedBorrower<T, R>;
+}
+
+impl<'a, T, R> Borrow<'a> for UpdateInheritedBorrower<T, R>
+where
+    T: Send + Sync + Component<Tracking = track::All>,
+    R: Relation,
+{
+    type View = UpdateInherited<'a, T, R>;
+
+    fn borrow(
+        world: &'a World,
+        last_run: Option<u32>,
+        current: u32,
+    ) -> Result<Self::View, error::GetStorage> {
+        Ok(UpdateInherited(
+            <View<T> as IntoBorrow>::Borrow::borrow(world, last_run, current)?,
+            <View<Reindexed<R>> as IntoBorrow>::Borrow::borrow(world, last_run, current)?,
+            <TreeView<R> as IntoBorrow>::Borrow::borrow(world, last_run, current)?,
+            <ViewMut<Inherited<T, R>> as IntoBorrow>::Borrow::borrow(world, last_run, current)?,
+        ))
+    }
+}
+
+unsafe impl<'a, T: Component<Tracking = track::All> + Send + Sync, R: Relation> BorrowInfo
+    for UpdateInherited<'a, T, R>
+{
+    fn borrow_info(mut info: &mut Vec<info::TypeInfo>) {
+        View::<'a, T>::borrow_info(&mut info);
+        View::<'a, Reindexed<R>>::borrow_info(&mut info);
+        TreeView::<'a, R>::borrow_info(&mut info);
+        ViewMut::<'a, Inherited<T, R>>::borrow_info(&mut info);
+    }
+}
@@ -0,0 +1,54 @@
+use super::*;
+
+/// A node of a tree with its children nested in order, e.g. to exchange outlines as nested JSON.
+///
+/// Only the order of children is kept, [Ordered] values are recreated by [import_nested].
+/// Implements `serde::Serialize` and `serde::Deserialize` with the `serde` feature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NestedNode<P> {
+    pub payload: P,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub children: Vec<NestedNode<P>>,
+}
+
+/// Nest `root` and its descendants from the indexes, with the `payload` of each node.
+pub fn export_nested<P, R: Relation>(
+    tree: &TreeView<R>,
+    root: EntityId,
+    payload: &mut impl FnMut(EntityId) -> P,
+) -> NestedNode<P> {
+    NestedNode {
+        payload: payload(root),
+        children: tree
+            .children(root)
+            .map(|child| export_nested(tree, child, payload))
+            .collect(),
+    }
+}
+
+/// Spawn an entity for `node` and each of its descendants with `spawn`, linking them with [ChildOf] in order.
+///
+/// Returns the entity spawned for `node`, which is left without a [ChildOf].
+/// The new [ChildOf]s are indexed by [tree_indexing] on the next update.
+pub fn import_nested<P, R: Relation>(
+    entities: &mut EntitiesViewMut,
+    vm_child_of: &mut ViewMut<ChildOf<R>>,
+    node: NestedNode<P>,
+    spawn: &mut impl FnMut(&mut EntitiesViewMut, P) -> EntityId,
+) -> EntityId {
+    let NestedNode { payload, children } = node;
+    let id = spawn(entities, payload);
+
+    let keys = OrderedRange::new(&Ordered::hinted(0), &Ordered::hinted(255))
+        .evenly_spaced_between(children.len());
+    for (child, key) in children.into_iter().zip(keys) {
+        let child_id = import_nested(entities, vm_child_of, child, spawn);
+        entities.add_component(child_id, &mut *vm_child_of, ChildOf::ordered(id, key));
+    }
+
+    id
+}
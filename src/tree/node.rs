@@ -0,0 +1,297 @@
+#![allow(dead_code)]
+use shipyard::{track, Component, EntityId};
+use std::marker::PhantomData;
+
+/// Names one hierarchy over the entities of a world, so the same entities can be arranged in several independent trees
+/// (e.g. a document outline and a layout), each with its own [ChildOf], indexes and [TreePlugin](super::TreePlugin).
+///
+/// Every tree type defaults to the [Hierarchy] relation.
+pub trait Relation:
+    Clone + Copy + std::fmt::Debug + Default + PartialEq + Eq + Send + Sync + 'static
+{
+}
+
+/// The default [Relation]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Hierarchy;
+
+impl Relation for Hierarchy {}
+
+/// ChildOf is the source of truth when it comes to the structure of things in trees.
+///
+/// .0 is parent EntityId, .1 is Ordered relative to siblings, .2 is the [Relation] of the tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChildOf<R = Hierarchy>(pub EntityId, pub Ordered, pub PhantomData<R>);
+
+impl<R: Relation> Component for ChildOf<R> {
+    type Tracking = track::All;
+}
+
+impl<R: Relation> ChildOf<R> {
+    pub fn new(child_of: EntityId, hint: u8) -> Self {
+        ChildOf::ordered(child_of, Ordered::hinted(hint))
+    }
+
+    /// Child of `child_of`, at `ordered` among its siblings
+    pub fn ordered(child_of: EntityId, ordered: Ordered) -> Self {
+        ChildOf(child_of, ordered, PhantomData)
+    }
+}
+
+/// A fractional index, the digits (base 256) after the point of a number between 0 and 1.
+///
+/// There is always another [Ordered] between two different [Ordered]s, so [Ordered::between], [Ordered::before]
+/// and [Ordered::after] never run out of precision, the keys just grow longer.
+/// Keys never end in a zero digit, so comparing the bytes compares the numbers.
+///
+/// Siblings with equal keys (e.g. moved to the same spot concurrently) are ordered by their [EntityId].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ordered(Vec<u8>);
+
+impl Ordered {
+    /// Create an ordered component with a hint of what it's initial order should be
+    pub fn hinted(hint: u8) -> Self {
+        // the trailing digit leaves room before a hint of 0 and between consecutive hints
+        Ordered(vec![hint, 0x80])
+    }
+
+    /// Mutate version of "between"
+    pub fn move_between(&mut self, min: &Self, max: &Self) {
+        *self = Ordered::between(min, max);
+    }
+
+    /// The digits of this key, never ending in zero
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Key between `min` and `max`, or equal to both if they are equal.
+    pub fn between(min: &Self, max: &Self) -> Self {
+        match min.cmp(max) {
+            std::cmp::Ordering::Less => Ordered(midpoint(&min.0, Some(&max.0))),
+            std::cmp::Ordering::Equal => min.clone(),
+            std::cmp::Ordering::Greater => Ordered(midpoint(&max.0, Some(&min.0))),
+        }
+    }
+
+    pub fn after(&self) -> Self {
+        Ordered(midpoint(&self.0, None))
+    }
+
+    pub fn before(&self) -> Self {
+        Ordered(midpoint(&[], Some(&self.0)))
+    }
+}
+
+/// Digits strictly between `lo` and `hi` (or 1 if `hi` is [None]), where `lo < hi` and neither ends in zero.
+fn midpoint(lo: &[u8], hi: Option<&[u8]>) -> Vec<u8> {
+    if let Some(hi) = hi {
+        // keep the common prefix, treating missing digits of lo as zeros
+        let common = hi
+            .iter()
+            .enumerate()
+            .take_while(|(idx, digit)| lo.get(*idx).copied().unwrap_or(0) == **digit)
+            .count();
+        if common > 0 {
+            let mut key = hi[..common].to_vec();
+            key.extend(midpoint(
+                lo.get(common..).unwrap_or(&[]),
+                Some(&hi[common..]),
+            ));
+            return key;
+        }
+    }
+
+    let lo_digit = lo.first().copied().unwrap_or(0) as u16;
+    let hi_digit = hi.map_or(256, |hi| hi[0] as u16);
+    if hi_digit - lo_digit > 1 {
+        // room for a single digit in between
+        vec![((lo_digit + hi_digit) / 2) as u8]
+    } else if let Some(hi) = hi.filter(|hi| hi.len() > 1) {
+        // hi's first digit alone is less than hi, and more than lo
+        vec![hi[0]]
+    } else {
+        // lo's first digit followed by anything after the rest of lo
+        let mut key = vec![lo_digit as u8];
+        key.extend(midpoint(lo.get(1..).unwrap_or(&[]), None));
+        key
+    }
+}
+
+pub struct OrderedRange(Ordered, Ordered);
+
+impl OrderedRange {
+    pub fn new(from: &Ordered, to: &Ordered) -> Self {
+        if from > to {
+            panic!("From value must be less than to value")
+        }
+        OrderedRange(from.clone(), to.clone())
+    }
+
+    /// Create a list of evenly spaced Ordered components within this OrderedRange.
+    /// The start and end points are not guaranteed to be the same as the `from` and `to`.
+    pub fn evenly_spaced_between(&self, length: usize) -> Vec<Ordered> {
+        // enough extra digits for length + 1 steps between any two different keys
+        let mut extra_digits = 1;
+        while 256u128.pow(extra_digits as u32) <= length as u128 + 1 {
+            extra_digits += 1;
+        }
+        let width = (self.0).0.len().max((self.1).0.len()) + extra_digits;
+
+        let from = padded(&(self.0).0, width);
+        let distance = sub(&padded(&(self.1).0, width), &from);
+        let step = div(&distance, length as u64 + 1);
+
+        let mut current = from;
+        (0..length)
+            .map(|_| {
+                current = add(&current, &step);
+                let mut key = current.clone();
+                while key.last() == Some(&0) {
+                    key.pop();
+                }
+                Ordered(key)
+            })
+            .collect()
+    }
+}
+
+// Fixed width, big endian arithmetic for spacing keys out evenly
+
+fn padded(digits: &[u8], width: usize) -> Vec<u8> {
+    let mut padded = digits.to_vec();
+    padded.resize(width, 0);
+    padded
+}
+
+fn add(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut sum = vec![0; a.len()];
+    let mut carry = 0u16;
+    for idx in (0..a.len()).rev() {
+        let digit = a[idx] as u16 + b[idx] as u16 + carry;
+        sum[idx] = digit as u8;
+        carry = digit >> 8;
+    }
+    sum
+}
+
+fn sub(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut difference = vec![0; a.len()];
+    let mut borrow = 0i16;
+    for idx in (0..a.len()).rev() {
+        let mut digit = a[idx] as i16 - b[idx] as i16 - borrow;
+        borrow = if digit < 0 {
+            digit += 256;
+            1
+        } else {
+            0
+        };
+        difference[idx] = digit as u8;
+    }
+    difference
+}
+
+fn div(a: &[u8], divisor: u64) -> Vec<u8> {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder = 0u128;
+    for (idx, digit) in a.iter().enumerate() {
+        let current = (remainder << 8) | *digit as u128;
+        quotient[idx] = (current / divisor as u128) as u8;
+        remainder = current % divisor as u128;
+    }
+    quotient
+}
+
+#[test]
+fn before_stays_above_zero() {
+    let mut ordered = Ordered::hinted(0);
+    for _ in 0..1000 {
+        let before = ordered.before();
+        assert!(before < ordered, "{:?} < {:?}", before, ordered);
+        assert_ne!(before.as_bytes().last(), Some(&0));
+        ordered = before;
+    }
+}
+
+#[test]
+fn hinted_orders_by_hint() {
+    assert!(Ordered::hinted(0) < Ordered::hinted(1));
+    assert!(Ordered::hinted(1) < Ordered::hinted(10));
+    assert!(Ordered::hinted(10) < Ordered::hinted(255));
+    assert_eq!(Ordered::hinted(3), Ordered(vec![3, 0x80]));
+}
+
+#[test]
+fn between_never_runs_out_of_precision() {
+    let min = Ordered::hinted(1);
+    let mut max = Ordered::hinted(2);
+    // always inserting at the same spot used to collide after ~32 moves
+    for _ in 0..1000 {
+        let between = Ordered::between(&min, &max);
+        assert!(min < between && between < max, "{:?}", between);
+        max = between;
+    }
+
+    let mut min = Ordered::hinted(1);
+    let max = Ordered::hinted(2);
+    for _ in 0..1000 {
+        let between = Ordered::between(&min, &max);
+        assert!(min < between && between < max, "{:?}", between);
+        min = between;
+    }
+}
+
+#[test]
+fn between_equal_keys_is_the_same_key() {
+    let ordered = Ordered::hinted(4);
+    assert_eq!(Ordered::between(&ordered, &ordered), ordered);
+}
+
+#[test]
+fn after_never_runs_out_of_precision() {
+    let mut ordered = Ordered::hinted(255);
+    for _ in 0..1000 {
+        let after = ordered.after();
+        assert!(after > ordered, "{:?} > {:?}", after, ordered);
+        ordered = after;
+    }
+}
+
+#[test]
+#[should_panic(expected = "From value must be less than to value")]
+fn ordered_range_must_not_be_greater_than_to() {
+    OrderedRange::new(&Ordered::hinted(2), &Ordered::hinted(1));
+}
+
+#[test]
+fn evenly_spaced_produces_a_vector_of_ordereds_with_one_distances() {
+    let range = OrderedRange::new(&Ordered(vec![1]), &Ordered(vec![5])).evenly_spaced_between(3);
+    assert_eq!(
+        range,
+        vec![Ordered(vec![2]), Ordered(vec![3]), Ordered(vec![4])]
+    );
+}
+
+#[test]
+fn evenly_spaced_between_produces_a_vector_of_ordereds_with_equal_distances() {
+    let range = OrderedRange::new(&Ordered(vec![1]), &Ordered(vec![10])).evenly_spaced_between(3);
+    assert_eq!(
+        range,
+        vec![
+            Ordered(vec![3, 64]),
+            Ordered(vec![5, 128]),
+            Ordered(vec![7, 192])
+        ]
+    );
+}
+
+#[test]
+fn evenly_spaced_between_adjacent_keys() {
+    let from = Ordered::hinted(1);
+    let to = Ordered::between(&from, &from.after());
+    let range = OrderedRange::new(&from, &to).evenly_spaced_between(1000);
+    assert_eq!(range.len(), 1000);
+    assert!(from < range[0]);
+    assert!(range.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(range[999] < to);
+}
@@ -0,0 +1,331 @@
+use super::*;
+use std::collections::HashSet;
+use tracing::*;
+
+/// Move `target` to `place`, see [MoveCommands]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MoveCmd {
+    pub target: EntityId,
+    pub place: MoveToPlace,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MoveToPlace {
+    Unlink,
+    After(EntityId),
+    Before(EntityId),
+    FirstChildOf(EntityId),
+    LastChildOf(EntityId),
+    /// Position among the other children of the parent, or last if past the end
+    AtIndex(EntityId, usize),
+    /// Exchange places with another entity, which may be a root. Like with [MoveToPlace::Unlink], a node becoming a root keeps its subtree
+    Swap(EntityId),
+}
+
+/// Why a [MoveCmd] was rejected by [tree_reordering], see [MoveErrors]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    /// The target or the entity it is placed relative to is not alive
+    DeadEntity { cmd: MoveCmd, entity: EntityId },
+    /// The new parent is the target itself or one of its descendants
+    CreatesCycle { cmd: MoveCmd, parent: EntityId },
+    /// [MoveToPlace::After] or [MoveToPlace::Before] points at an entity without a [SiblingIndex], perhaps an indexing step was missed
+    UnindexedSibling { cmd: MoveCmd, sibling: EntityId },
+}
+
+impl MoveError {
+    pub fn cmd(&self) -> &MoveCmd {
+        match self {
+            MoveError::DeadEntity { cmd, .. }
+            | MoveError::CreatesCycle { cmd, .. }
+            | MoveError::UnindexedSibling { cmd, .. } => cmd,
+        }
+    }
+}
+
+/// Applies [MoveCommands] in order, reindexing after each move so later commands can build on earlier ones.
+/// The resulting [ChildOf] changes are kept in [MoveCommands::applied] until the next run.
+///
+/// [tree_indexing] still picks up the [ChildOf] changes afterwards, finding the indexes already up to date.
+pub fn tree_reordering<R: Relation>(
+    (
+        v_entities,
+        mut commands,
+        mut errors,
+        mut vm_child_of,
+        mut vm_parent_index,
+        mut vm_sibling_index,
+    ): (
+        EntitiesView,
+        UniqueViewMut<MoveCommands<R>>,
+        UniqueViewMut<MoveErrors<R>>,
+        ViewMut<ChildOf<R>>,
+        ViewMut<ParentIndex<R>>,
+        ViewMut<SiblingIndex<R>>,
+    ),
+) {
+    let pending = commands.drain(..).collect::<Vec<_>>();
+    commands.applied.clear();
+
+    for cmd in pending {
+        let span = info_span!("applying move command", ?cmd);
+        let _entered = span.enter();
+        if let Some(parent_id) = parent_with_tied_neighbours(&vm_sibling_index, &cmd) {
+            // nothing fits between two equal keys, so give the siblings distinct keys first
+            indexing::rebalance_children(
+                &mut vm_child_of,
+                &mut vm_sibling_index,
+                &mut vm_parent_index,
+                parent_id,
+            );
+        }
+        match validated_changes(&v_entities, &vm_parent_index, &vm_sibling_index, &cmd) {
+            Ok(changes) => {
+                for (target, change) in changes {
+                    apply_change(
+                        &v_entities,
+                        &mut vm_child_of,
+                        &mut vm_parent_index,
+                        &mut vm_sibling_index,
+                        target,
+                        change.clone(),
+                    );
+                    commands.applied.push((target, change));
+                }
+            }
+            Err(error) => {
+                warn!(?error, "rejected move command");
+                errors.push(error);
+            }
+        }
+    }
+}
+
+/// Set (or delete if [None]) the [ChildOf] of `target`, and reindex it right away.
+fn apply_change<R: Relation>(
+    v_entities: &EntitiesView,
+    vm_child_of: &mut ViewMut<ChildOf<R>>,
+    vm_parent_index: &mut ViewMut<ParentIndex<R>>,
+    vm_sibling_index: &mut ViewMut<SiblingIndex<R>>,
+    target: EntityId,
+    change: Option<ChildOf<R>>,
+) {
+    match change {
+        Some(child_of) => {
+            let ChildOf(parent_id, child_order, _) = child_of.clone();
+            if vm_child_of.contains(target) {
+                *(&mut *vm_child_of).get(target).unwrap() = child_of;
+            } else {
+                v_entities.add_component(target, &mut *vm_child_of, child_of);
+            }
+
+            // rebalancing is left to tree_indexing, which reindexes the changed ChildOf
+            indexing::insert_child_of(
+                v_entities,
+                vm_sibling_index,
+                vm_parent_index,
+                target,
+                &child_order,
+                parent_id,
+            );
+        }
+        None => {
+            // deletion is tracked, but tree_indexing finds the target already unlinked
+            vm_child_of.delete(target);
+            indexing::unlink_child(vm_sibling_index, vm_parent_index, target);
+        }
+    }
+}
+
+/// The [ChildOf] each entity moved by `cmd` should have after the move, or [None] if it should be unlinked.
+fn validated_changes<R: Relation>(
+    v_entities: &EntitiesView,
+    v_parent_index: &ViewMut<ParentIndex<R>>,
+    v_sibling_index: &ViewMut<SiblingIndex<R>>,
+    cmd: &MoveCmd,
+) -> Result<Vec<(EntityId, Option<ChildOf<R>>)>, MoveError> {
+    let ensure_alive = |entity: EntityId| {
+        if v_entities.is_alive(entity) {
+            Ok(())
+        } else {
+            Err(MoveError::DeadEntity {
+                cmd: cmd.clone(),
+                entity,
+            })
+        }
+    };
+    let indexed_sibling = |sibling: EntityId| {
+        v_sibling_index
+            .get(sibling)
+            .map_err(|_| MoveError::UnindexedSibling {
+                cmd: cmd.clone(),
+                sibling,
+            })
+    };
+    // other children of parent in order, without the target
+    let other_children = |parent: EntityId| {
+        v_parent_index
+            .get(parent)
+            .map(|parent_index: &ParentIndex<R>| {
+                parent_index
+                    .children
+                    .iter()
+                    .filter(|(_, id)| id != &cmd.target)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+    let ensure_no_cycle = |parent: EntityId| {
+        if is_self_or_descendant(v_sibling_index, cmd.target, parent) {
+            Err(MoveError::CreatesCycle {
+                cmd: cmd.clone(),
+                parent,
+            })
+        } else {
+            Ok(())
+        }
+    };
+
+    ensure_alive(cmd.target)?;
+    let child_of = match cmd.place {
+        MoveToPlace::After(a) => {
+            ensure_alive(a)?;
+            let sibling = indexed_sibling(a)?;
+            let (a_ord, _) = &sibling.ordered_node;
+            ensure_no_cycle(sibling.parent_node)?;
+
+            let new_ord = match &sibling.next_sibling {
+                Some((next_ord, _)) => Ordered::between(a_ord, next_ord),
+                None => Ordered::after(a_ord),
+            };
+
+            ChildOf::ordered(sibling.parent_node, new_ord)
+        }
+        MoveToPlace::Before(b) => {
+            ensure_alive(b)?;
+            let sibling = indexed_sibling(b)?;
+            let (b_ord, _) = &sibling.ordered_node;
+            ensure_no_cycle(sibling.parent_node)?;
+
+            let new_ord = match &sibling.prev_sibling {
+                Some((prev_ord, _)) => Ordered::between(prev_ord, b_ord),
+                None => Ordered::before(b_ord),
+            };
+
+            ChildOf::ordered(sibling.parent_node, new_ord)
+        }
+        MoveToPlace::FirstChildOf(parent) => {
+            ensure_alive(parent)?;
+            ensure_no_cycle(parent)?;
+            v_parent_index
+                .get(parent)
+                .ok()
+                .and_then(|parent_index: &ParentIndex<R>| parent_index.children.first())
+                .map(|first_child| ChildOf::ordered(parent, Ordered::before(&first_child.0)))
+                // found no first child in index, create new ChildOf
+                .unwrap_or_else(|| ChildOf::new(parent, 0))
+        }
+        MoveToPlace::LastChildOf(parent) => {
+            ensure_alive(parent)?;
+            ensure_no_cycle(parent)?;
+            v_parent_index
+                .get(parent)
+                .ok()
+                .and_then(|parent_index: &ParentIndex<R>| parent_index.children.last())
+                .map(|last_child| ChildOf::ordered(parent, Ordered::after(&last_child.0)))
+                // found no last child in index, create new ChildOf
+                .unwrap_or_else(|| ChildOf::new(parent, 0))
+        }
+        MoveToPlace::AtIndex(parent, index) => {
+            ensure_alive(parent)?;
+            ensure_no_cycle(parent)?;
+            let children = other_children(parent);
+            let index = index.min(children.len());
+            let new_ord = match (
+                index.checked_sub(1).map(|prev| &children[prev].0),
+                children.get(index).map(|(next_ord, _)| next_ord),
+            ) {
+                (Some(prev_ord), Some(next_ord)) => Ordered::between(prev_ord, next_ord),
+                (Some(prev_ord), None) => Ordered::after(prev_ord),
+                (None, Some(next_ord)) => Ordered::before(next_ord),
+                (None, None) => Ordered::hinted(0),
+            };
+            ChildOf::ordered(parent, new_ord)
+        }
+        MoveToPlace::Swap(other) => {
+            ensure_alive(other)?;
+            // roots have no place in a parent, and are swapped for being roots
+            let place_of = |id: EntityId| {
+                v_sibling_index
+                    .get(id)
+                    .ok()
+                    .map(|sibling: &SiblingIndex<R>| {
+                        ChildOf::ordered(sibling.parent_node, sibling.ordered_node.0.clone())
+                    })
+            };
+            let (target_place, other_place) = (place_of(cmd.target), place_of(other));
+            if let Some(ChildOf(parent, ..)) = &other_place {
+                ensure_no_cycle(*parent)?;
+            }
+            if let Some(ChildOf(parent, ..)) = &target_place {
+                if is_self_or_descendant(v_sibling_index, other, *parent) {
+                    return Err(MoveError::CreatesCycle {
+                        cmd: cmd.clone(),
+                        parent: *parent,
+                    });
+                }
+            }
+            return Ok(vec![(cmd.target, other_place), (other, target_place)]);
+        }
+        MoveToPlace::Unlink => return Ok(vec![(cmd.target, None)]),
+    };
+
+    Ok(vec![(cmd.target, Some(child_of))])
+}
+
+/// The parent of the sibling `cmd` places its target next to, if that sibling shares its key with the neighbour on that side.
+fn parent_with_tied_neighbours<R: Relation>(
+    v_sibling_index: &ViewMut<SiblingIndex<R>>,
+    cmd: &MoveCmd,
+) -> Option<EntityId> {
+    let (sibling, neighbour) = match cmd.place {
+        MoveToPlace::After(a) => {
+            let sibling = v_sibling_index.get(a).ok()?;
+            (sibling, sibling.next_sibling.as_ref()?)
+        }
+        MoveToPlace::Before(b) => {
+            let sibling = v_sibling_index.get(b).ok()?;
+            (sibling, sibling.prev_sibling.as_ref()?)
+        }
+        _ => return None,
+    };
+    if sibling.ordered_node.0 == neighbour.0 {
+        Some(sibling.parent_node)
+    } else {
+        None
+    }
+}
+
+/// Whether `entity` is `ancestor` or one of its descendants, walking up the indexed parents of `entity`.
+fn is_self_or_descendant<R: Relation>(
+    v_sibling_index: &ViewMut<SiblingIndex<R>>,
+    ancestor: EntityId,
+    entity: EntityId,
+) -> bool {
+    let mut visited = HashSet::new();
+    let mut current = entity;
+    loop {
+        if current == ancestor {
+            return true;
+        }
+        if !visited.insert(current) {
+            // the index already loops without reaching ancestor
+            return false;
+        }
+        match v_sibling_index.get(current) {
+            Ok(sibling) => current = sibling.parent_node,
+            Err(_) => return false,
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use super::*;
+use std::collections::{HashMap, HashSet};
+use std::iter::once;
+use tracing::*;
+
+/// Identifies a replica of the tree, must be unique among the replicas merging each other's moves
+pub type ReplicaId = u64;
+
+/// Lamport timestamp of a [ReplicatedMove].
+///
+/// Ordered by clock first and replica second, so every replica agrees on the order of all moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    pub clock: u64,
+    pub replica: ReplicaId,
+}
+
+/// A move of `target` to `child_of` (or unlinking it if [None]), as exchanged between replicas.
+///
+/// Carries the resolved [ChildOf] instead of a [MoveToPlace], since places relative to siblings
+/// depend on the tree of the replica which made the move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplicatedMove<R = Hierarchy> {
+    pub timestamp: Timestamp,
+    pub target: EntityId,
+    pub child_of: Option<ChildOf<R>>,
+}
+
+/// Log of every [ReplicatedMove] seen by a replica, and the tree they result in.
+///
+/// Moves are applied in [Timestamp] order: merging a move older than moves already applied undoes the newer moves,
+/// applies the older one and redoes the newer ones on top. A move which would make its target an ancestor of itself
+/// is skipped, so replicas which merged the same moves end up with the same acyclic tree, whatever order they merged them in.
+///
+/// Every merged move is kept to be undone and redone, until [MoveLog::truncate_before] drops the causally stable ones.
+#[derive(Debug)]
+pub struct MoveLog<R = Hierarchy> {
+    replica: ReplicaId,
+    clock: u64,
+    // ordered by timestamp, along with the ChildOf the target had before the move
+    applied: Vec<(ReplicatedMove<R>, Option<ChildOf<R>>)>,
+    // moves before this were dropped from `applied`
+    truncated: Option<Timestamp>,
+    tree: HashMap<EntityId, ChildOf<R>>,
+}
+
+impl<R: Relation> MoveLog<R> {
+    pub fn new(replica: ReplicaId) -> Self {
+        MoveLog {
+            replica,
+            clock: 0,
+            applied: Vec::new(),
+            truncated: None,
+            tree: HashMap::new(),
+        }
+    }
+
+    pub fn replica(&self) -> ReplicaId {
+        self.replica
+    }
+
+    /// Latest clock made or merged by this replica
+    pub fn clock(&self) -> u64 {
+        self.clock
+    }
+
+    /// [ChildOf] of `target` after all merged moves
+    pub fn child_of(&self, target: EntityId) -> Option<&ChildOf<R>> {
+        self.tree.get(&target)
+    }
+
+    /// Number of moves kept to merge older moves with
+    pub fn len(&self) -> usize {
+        self.applied.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.applied.is_empty()
+    }
+
+    /// Drop the moves older than `stable`, which can no longer be undone by merging an older move.
+    ///
+    /// `stable` must be causally stable: every replica has merged every move before it, e.g. the lowest of the latest
+    /// timestamps received from each replica, when each replica's moves arrive in order. Moves older than `stable`
+    /// merged afterwards are ignored.
+    pub fn truncate_before(&mut self, stable: Timestamp) {
+        let kept = self
+            .applied
+            .partition_point(|(applied, _)| applied.timestamp < stable);
+        trace!(dropped = kept, ?stable, "truncating move log");
+        self.applied.drain(..kept);
+        self.truncated = self.truncated.max(Some(stable));
+    }
+
+    /// Every linked entity with its [ChildOf], in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &ChildOf<R>)> {
+        self.tree.iter().map(|(id, child_of)| (*id, child_of))
+    }
+
+    /// Timestamp and apply a move made by this replica, returning it to be sent to the other replicas.
+    pub fn local_move(
+        &mut self,
+        target: EntityId,
+        child_of: Option<ChildOf<R>>,
+    ) -> ReplicatedMove<R> {
+        self.clock += 1;
+        let replicated = ReplicatedMove {
+            timestamp: Timestamp {
+                clock: self.clock,
+                replica: self.replica,
+            },
+            target,
+            child_of,
+        };
+        self.merge(replicated.clone());
+        replicated
+    }
+
+    /// Apply a move made by any replica, returning the entities whose [ChildOf] changed as a result.
+    ///
+    /// Merging a move which was already merged, or which is older than [MoveLog::truncate_before] allows, does nothing.
+    pub fn merge(&mut self, replicated: ReplicatedMove<R>) -> Vec<EntityId> {
+        if Some(replicated.timestamp) < self.truncated {
+            warn!(?replicated, truncated = ?self.truncated, "ignoring move older than the truncated log");
+            return Vec::new();
+        }
+        self.clock = self.clock.max(replicated.timestamp.clock);
+        let at = match self
+            .applied
+            .binary_search_by_key(&replicated.timestamp, |(applied, _)| applied.timestamp)
+        {
+            Ok(_) => return Vec::new(),
+            Err(at) => at,
+        };
+
+        let later = self.applied.split_off(at);
+        let mut targets = later
+            .iter()
+            .map(|(applied, _)| applied.target)
+            .chain(once(replicated.target))
+            .collect::<Vec<_>>();
+        targets.sort();
+        targets.dedup();
+        let before = targets
+            .iter()
+            .map(|target| self.tree.get(target).cloned())
+            .collect::<Vec<_>>();
+
+        // newest first, back to the tree as it was before this move
+        for (applied, previous) in later.iter().rev() {
+            self.restore(applied.target, previous.clone());
+        }
+        for applied in once(replicated).chain(later.into_iter().map(|(applied, _)| applied)) {
+            let previous = self.apply(&applied);
+            self.applied.push((applied, previous));
+        }
+
+        targets
+            .into_iter()
+            .zip(before)
+            .filter(|(target, before)| self.tree.get(target) != before.as_ref())
+            .map(|(target, _)| target)
+            .collect()
+    }
+
+    /// Returns the [ChildOf] the target had before, to undo the move with.
+    fn apply(&mut self, replicated: &ReplicatedMove<R>) -> Option<ChildOf<R>> {
+        let previous = self.tree.get(&replicated.target).cloned();
+        match &replicated.child_of {
+            Some(child_of) if self.is_self_or_ancestor(replicated.target, child_of.0) => {
+                trace!(?replicated, "skipping move which would create a cycle");
+            }
+            Some(child_of) => {
+                self.tree.insert(replicated.target, child_of.clone());
+            }
+            None => {
+                self.tree.remove(&replicated.target);
+            }
+        }
+        previous
+    }
+
+    fn restore(&mut self, target: EntityId, previous: Option<ChildOf<R>>) {
+        match previous {
+            Some(child_of) => self.tree.insert(target, child_of),
+            None => self.tree.remove(&target),
+        };
+    }
+
+    /// Whether `ancestor` is `entity` or one of its ancestors, the tree is always acyclic.
+    fn is_self_or_ancestor(&self, ancestor: EntityId, entity: EntityId) -> bool {
+        let mut current = entity;
+        loop {
+            if current == ancestor {
+                return true;
+            }
+            match self.tree.get(&current) {
+                Some(ChildOf(parent, ..)) => current = *parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+/// Records [ChildOf] changes made since the last update as local moves in [TreeReplica::outgoing], then merges
+/// [TreeReplica::incoming] moves, updating [ChildOf] to match the merged tree.
+///
+/// Moves applied by [tree_reordering] are recorded in the order they were made, see [MoveCommands::applied].
+/// [ChildOf] changed directly has no known order: deletions are recorded first, so unlinking a node and moving
+/// its former parent under it is not mistaken for a cycle, then the other changes by [EntityId].
+///
+/// Runs before [tree_indexing], which indexes both local and merged changes.
+pub fn tree_replication<R: Relation>(
+    (v_entities, commands, mut replica, mut vm_child_of): (
+        EntitiesView,
+        UniqueView<MoveCommands<R>>,
+        UniqueViewMut<TreeReplica<R>>,
+        ViewMut<ChildOf<R>>,
+    ),
+) {
+    let replica = &mut *replica;
+
+    let mut touched = HashSet::new();
+    let mut record =
+        |replica: &mut TreeReplica<R>, target: EntityId, child_of: Option<ChildOf<R>>| {
+            touched.insert(target);
+            let replicated = replica.log.local_move(target, child_of);
+            replica.outgoing.push(replicated);
+        };
+
+    for (target, child_of) in commands.applied() {
+        if replica.log.child_of(*target) != child_of.as_ref() {
+            record(replica, *target, child_of.clone());
+        }
+    }
+
+    // made directly or by tree_cascading, not yet known to the log.
+    // deleted ChildOf are left for tree_indexing to take
+    let mut unlinked = vm_child_of
+        .deleted()
+        .iter()
+        .filter(|(id, _)| !vm_child_of.contains(*id) && replica.log.child_of(*id).is_some())
+        .map(|(id, _)| *id)
+        .collect::<Vec<_>>();
+    unlinked.sort();
+    unlinked.dedup();
+    for target in unlinked {
+        record(replica, target, None);
+    }
+    let mut linked = vm_child_of
+        .inserted_or_modified()
+        .iter()
+        .with_id()
+        .filter(|(id, child_of)| replica.log.child_of(*id) != Some(*child_of))
+        .map(|(id, child_of)| (id, child_of.clone()))
+        .collect::<Vec<_>>();
+    linked.sort_by_key(|(id, _)| *id);
+    for (target, child_of) in linked {
+        record(replica, target, Some(child_of));
+    }
+
+    for replicated in std::mem::take(&mut replica.incoming) {
+        let span = trace_span!("merging move", ?replicated);
+        let _entered = span.enter();
+        touched.extend(replica.log.merge(replicated));
+    }
+
+    // local moves may have lost to concurrent moves too
+    for target in touched {
+        let merged = replica.log.child_of(target);
+        if (&vm_child_of).get(target).ok() == merged {
+            continue;
+        }
+        match merged {
+            Some(child_of) if vm_child_of.contains(target) => {
+                *(&mut vm_child_of).get(target).unwrap() = child_of.clone();
+            }
+            Some(child_of) => {
+                if v_entities.is_alive(target) {
+                    v_entities.add_component(target, &mut vm_child_of, child_of.clone());
+                }
+            }
+            None => {
+                vm_child_of.delete(target);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entities(count: usize) -> Vec<EntityId> {
+        let world = World::new();
+        world
+            .run(|mut entities: EntitiesViewMut| {
+                (0..count).map(|_| entities.add_entity((), ())).collect()
+            })
+            .unwrap()
+    }
+
+    fn moved(clock: u64, replica: ReplicaId, target: EntityId, parent: EntityId) -> ReplicatedMove {
+        ReplicatedMove {
+            timestamp: Timestamp { clock, replica },
+            target,
+            child_of: Some(ChildOf::new(parent, clock as u8)),
+        }
+    }
+
+    fn sorted_tree(log: &MoveLog) -> Vec<(EntityId, ChildOf)> {
+        let mut tree = log
+            .iter()
+            .map(|(id, child_of)| (id, child_of.clone()))
+            .collect::<Vec<_>>();
+        tree.sort_by_key(|(id, _)| *id);
+        tree
+    }
+
+    fn permutations(moves: &[ReplicatedMove]) -> Vec<Vec<ReplicatedMove>> {
+        if moves.len() <= 1 {
+            return vec![moves.to_vec()];
+        }
+        let mut all = Vec::new();
+        for idx in 0..moves.len() {
+            let mut rest = moves.to_vec();
+            let first = rest.remove(idx);
+            for mut permutation in permutations(&rest) {
+                permutation.insert(0, first.clone());
+                all.push(permutation);
+            }
+        }
+        all
+    }
+
+    #[test]
+    fn replicas_converge_in_any_order() {
+        let ids = entities(4);
+        let (root, a, b, c) = (ids[0], ids[1], ids[2], ids[3]);
+        let moves = vec![
+            moved(1, 1, a, root),
+            moved(1, 2, b, root),
+            moved(2, 1, c, a),
+            // concurrent moves of c
+            moved(3, 1, c, b),
+            moved(3, 2, c, root),
+            ReplicatedMove {
+                timestamp: Timestamp {
+                    clock: 4,
+                    replica: 2,
+                },
+                target: b,
+                child_of: None,
+            },
+        ];
+
+        let mut expected = MoveLog::new(3);
+        for replicated in moves.iter().cloned() {
+            expected.merge(replicated);
+        }
+        let expected = sorted_tree(&expected);
+        assert_eq!(
+            expected.iter().find(|(id, _)| *id == c).unwrap().1 .0,
+            root,
+            "latest concurrent move wins"
+        );
+
+        for permutation in permutations(&moves) {
+            let mut log = MoveLog::new(3);
+            for replicated in permutation.iter().cloned() {
+                log.merge(replicated);
+            }
+            assert_eq!(sorted_tree(&log), expected, "merged in {:?}", permutation);
+        }
+    }
+
+    #[test]
+    fn concurrent_moves_do_not_form_cycles() {
+        let ids = entities(3);
+        let (root, a, b) = (ids[0], ids[1], ids[2]);
+
+        let mut replica_1 = MoveLog::new(1);
+        let mut replica_2 = MoveLog::new(2);
+        for log in [&mut replica_1, &mut replica_2].iter_mut() {
+            log.merge(moved(1, 0, a, root));
+            log.merge(moved(2, 0, b, root));
+        }
+
+        // each moves one under the other before hearing about the other move
+        let a_under_b = replica_1.local_move(a, Some(ChildOf::new(b, 0)));
+        let b_under_a = replica_2.local_move(b, Some(ChildOf::new(a, 0)));
+        assert_eq!(replica_1.merge(b_under_a), Vec::<EntityId>::new());
+        assert_eq!(replica_2.merge(a_under_b), vec![a, b]);
+
+        assert_eq!(sorted_tree(&replica_1), sorted_tree(&replica_2));
+        assert_eq!(replica_1.child_of(a).unwrap().0, b);
+        assert_eq!(replica_1.child_of(b).unwrap().0, root);
+    }
+
+    #[test]
+    fn truncate_stable_moves() {
+        let ids = entities(3);
+        let (root, a, b) = (ids[0], ids[1], ids[2]);
+        let mut log = MoveLog::new(1);
+        let first = moved(1, 2, a, root);
+        log.merge(first.clone());
+        log.merge(moved(2, 2, b, root));
+        log.merge(moved(3, 1, b, a));
+
+        // every replica has seen the moves before clock 3
+        log.truncate_before(Timestamp {
+            clock: 3,
+            replica: 0,
+        });
+        assert_eq!(log.len(), 1);
+        assert_eq!(log.child_of(b).unwrap().0, a);
+
+        // the kept move is still undone for concurrent moves, truncated moves are not merged again
+        assert_eq!(log.merge(moved(3, 2, b, root)), vec![b]);
+        assert_eq!(log.child_of(b).unwrap().0, root);
+        assert_eq!(log.merge(first), Vec::<EntityId>::new());
+        assert_eq!(log.len(), 2);
+    }
+
+    #[test]
+    fn merging_twice_changes_nothing() {
+        let ids = entities(2);
+        let mut log = MoveLog::new(1);
+        let replicated = moved(5, 2, ids[1], ids[0]);
+        assert_eq!(log.merge(replicated.clone()), vec![ids[1]]);
+        assert_eq!(log.merge(replicated), Vec::<EntityId>::new());
+        // later local moves are timestamped after everything merged
+        assert_eq!(log.local_move(ids[1], None).timestamp.clock, 6);
+    }
+}
@@ -0,0 +1,179 @@
+use super::*;
+use std::collections::{HashSet, VecDeque};
+
+/// Walk the tree through [ParentIndex] and [SiblingIndex], as indexed by [tree_indexing].
+///
+/// Entities without indexes are treated as roots without children.
+/// Walks visit each node at most once, so they end even if [ChildOf] has cycles, e.g. `ChildOf(a, a)`.
+pub struct TreeView<'a, R: Relation = Hierarchy>(
+    View<'a, ParentIndex<R>>,
+    View<'a, SiblingIndex<R>>,
+);
+
+impl<'a, R: Relation> TreeView<'a, R> {
+    pub fn parent(&self, id: EntityId) -> Option<EntityId> {
+        (&self.1)
+            .get(id)
+            .ok()
+            .map(|sibling_index| sibling_index.parent_node)
+    }
+
+    /// Direct children of `id`, in order
+    pub fn children(&self, id: EntityId) -> impl Iterator<Item = EntityId> + '_ {
+        (&self.0)
+            .get(id)
+            .ok()
+            .into_iter()
+            .flat_map(|parent_index| parent_index.children.iter().map(|(_, child)| *child))
+    }
+
+    /// Parent of `id`, its parent, and so on up to the root
+    pub fn ancestors(&self, id: EntityId) -> Ancestors<'_, 'a, R> {
+        Ancestors {
+            tree: self,
+            current: id,
+            visited: std::iter::once(id).collect(),
+        }
+    }
+
+    /// Descendants of `id` in depth first pre-order, not including `id`
+    pub fn descendants_depth_first(&self, id: EntityId) -> DepthFirst<'_, 'a, R> {
+        let mut stack = self.children(id).collect::<Vec<_>>();
+        stack.reverse();
+        DepthFirst {
+            tree: self,
+            stack,
+            visited: std::iter::once(id).collect(),
+        }
+    }
+
+    /// Descendants of `id` level by level, not including `id`
+    pub fn descendants_breadth_first(&self, id: EntityId) -> BreadthFirst<'_, 'a, R> {
+        BreadthFirst {
+            tree: self,
+            queue: self.children(id).collect(),
+            visited: std::iter::once(id).collect(),
+        }
+    }
+
+    /// Number of ancestors of `id`, 0 for roots
+    pub fn depth_of(&self, id: EntityId) -> usize {
+        self.ancestors(id).count()
+    }
+
+    /// Topmost ancestor of `id`, or `id` itself if it has no parent
+    pub fn root_of(&self, id: EntityId) -> EntityId {
+        self.ancestors(id).last().unwrap_or(id)
+    }
+
+    /// Ancestors of `id` starting from the root, followed by `id`
+    pub fn path_from_root(&self, id: EntityId) -> Vec<EntityId> {
+        let mut path = self.ancestors(id).collect::<Vec<_>>();
+        path.reverse();
+        path.push(id);
+        path
+    }
+
+    /// Deepest entity which is `a` or one of its ancestors and `b` or one of its ancestors, [None] if they are in different trees
+    pub fn lowest_common_ancestor(&self, a: EntityId, b: EntityId) -> Option<EntityId> {
+        self.path_from_root(a)
+            .into_iter()
+            .zip(self.path_from_root(b))
+            .take_while(|(a, b)| a == b)
+            .last()
+            .map(|(common, _)| common)
+    }
+}
+
+/// See [TreeView::ancestors]
+pub struct Ancestors<'t, 'a, R: Relation = Hierarchy> {
+    tree: &'t TreeView<'a, R>,
+    current: EntityId,
+    visited: HashSet<EntityId>,
+}
+
+impl<R: Relation> Iterator for Ancestors<'_, '_, R> {
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let parent = self.tree.parent(self.current)?;
+        if !self.visited.insert(parent) {
+            // back at a node of a cycle
+            return None;
+        }
+        self.current = parent;
+        Some(parent)
+    }
+}
+
+/// See [TreeView::descendants_depth_first]
+pub struct DepthFirst<'t, 'a, R: Relation = Hierarchy> {
+    tree: &'t TreeView<'a, R>,
+    stack: Vec<EntityId>,
+    visited: HashSet<EntityId>,
+}
+
+impl<R: Relation> Iterator for DepthFirst<'_, '_, R> {
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(next) = self.stack.pop() {
+            if self.visited.insert(next) {
+                let first_child_on_top = self.stack.len();
+                self.stack.extend(self.tree.children(next));
+                self.stack[first_child_on_top..].reverse();
+                return Some(next);
+            }
+        }
+        None
+    }
+}
+
+/// See [TreeView::descendants_breadth_first]
+pub struct BreadthFirst<'t, 'a, R: Relation = Hierarchy> {
+    tree: &'t TreeView<'a, R>,
+    queue: VecDeque<EntityId>,
+    visited: HashSet<EntityId>,
+}
+
+impl<R: Relation> Iterator for BreadthFirst<'_, '_, R> {
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(next) = self.queue.pop_front() {
+            if self.visited.insert(next) {
+                self.queue.extend(self.tree.children(next));
+                return Some(next);
+            }
+        }
+        None
+    }
+}
+
+pub struct TreeViewBorrower<R>(R);
+
+impl<R: Relation> IntoBorrow for TreeView<'_, R> {
+    type Borrow = TreeViewBorrower<R>;
+}
+
+impl<'a, R: Relation> Borrow<'a> for TreeViewBorrower<R> {
+    type View = TreeView<'a, R>;
+
+    fn borrow(
+        world: &'a World,
+        last_run: Option<u32>,
+        current: u32,
+    ) -> Result<Self::View, error::GetStorage> {
+        Ok(TreeView(
+            <View<ParentIndex<R>> as IntoBorrow>::Borrow::borrow(world, last_run, current)?,
+            <View<SiblingIndex<R>> as IntoBorrow>::Borrow::borrow(world, last_run, current)?,
+        ))
+    }
+}
+
+unsafe impl<'a, R: Relation> BorrowInfo for TreeView<'a, R> {
+    fn borrow_info(mut info: &mut Vec<info::TypeInfo>) {
+        View::<'a, ParentIndex<R>>::borrow_info(&mut info);
+        View::<'a, SiblingIndex<R>>::borrow_info(&mut info);
+    }
+}
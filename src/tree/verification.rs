@@ -0,0 +1,225 @@
+use super::*;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use tracing::*;
+
+/// A difference between the indexes kept by [tree_indexing] and the indexes recomputed from [ChildOf], see [verify_tree_indexes]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IndexDiscrepancy<R = Hierarchy> {
+    /// A parent of children with [ChildOf] has no [ParentIndex]
+    MissingParentIndex { parent: EntityId },
+    /// A [ParentIndex] lists other children, or lists them in another order
+    WrongChildren {
+        parent: EntityId,
+        expected: Vec<(Ordered, EntityId)>,
+        found: Vec<(Ordered, EntityId)>,
+    },
+    /// A child with [ChildOf] has no [SiblingIndex]
+    MissingSiblingIndex { child: EntityId },
+    /// A [SiblingIndex] points at another parent or other siblings
+    WrongSiblingIndex {
+        child: EntityId,
+        expected: SiblingIndex<R>,
+        found: SiblingIndex<R>,
+    },
+    /// An entity without a [ChildOf] has a [SiblingIndex]
+    UnexpectedSiblingIndex { child: EntityId },
+}
+
+/// Children of each parent according to [ChildOf], in order.
+///
+/// Like with [tree_indexing], children of a deleted parent stay indexed as siblings, but the parent has no [ParentIndex].
+fn expected_children<R: Relation>(
+    child_of: &View<ChildOf<R>>,
+) -> HashMap<EntityId, Vec<(Ordered, EntityId)>> {
+    let mut children: HashMap<EntityId, Vec<(Ordered, EntityId)>> = HashMap::new();
+    for (id, ChildOf(parent_id, ordered, _)) in child_of.iter().with_id() {
+        children
+            .entry(*parent_id)
+            .or_default()
+            .push((ordered.clone(), id));
+    }
+    for siblings in children.values_mut() {
+        siblings.sort();
+    }
+    children
+}
+
+fn expected_sibling_index<R: Relation>(
+    parent_id: EntityId,
+    children: &[(Ordered, EntityId)],
+    idx: usize,
+) -> SiblingIndex<R> {
+    SiblingIndex {
+        parent_node: parent_id,
+        ordered_node: children[idx].clone(),
+        prev_sibling: idx.checked_sub(1).map(|prev| children[prev].clone()),
+        next_sibling: children.get(idx + 1).cloned(),
+        relation: PhantomData,
+    }
+}
+
+/// Compare [ParentIndex] and [SiblingIndex] against indexes recomputed from scratch from [ChildOf].
+///
+/// Run with `app.run(verify_tree_indexes::<R>)`, and heal the indexes with [tree_index_rebuilding].
+pub fn verify_tree_indexes<R: Relation>(
+    (v_entities, v_child_of, v_parent_index, v_sibling_index): (
+        EntitiesView,
+        View<ChildOf<R>>,
+        View<ParentIndex<R>>,
+        View<SiblingIndex<R>>,
+    ),
+) -> Vec<IndexDiscrepancy<R>> {
+    let expected = expected_children(&v_child_of);
+    let mut discrepancies = Vec::new();
+
+    let mut parents = expected.keys().copied().collect::<Vec<_>>();
+    parents.sort();
+    for parent_id in parents {
+        let children = &expected[&parent_id];
+        match v_parent_index.get(parent_id) {
+            _ if !v_entities.is_alive(parent_id) => {}
+            Ok(parent_index) if !parent_index.children.iter().eq(children.iter()) => discrepancies
+                .push(IndexDiscrepancy::WrongChildren {
+                    parent: parent_id,
+                    expected: children.clone(),
+                    found: parent_index.children.iter().cloned().collect(),
+                }),
+            Ok(_) => {}
+            Err(_) => {
+                discrepancies.push(IndexDiscrepancy::MissingParentIndex { parent: parent_id })
+            }
+        }
+
+        for (idx, (_, child)) in children.iter().enumerate() {
+            let expected_index = expected_sibling_index(parent_id, children, idx);
+            match v_sibling_index.get(*child) {
+                Ok(found) if found != &expected_index => {
+                    discrepancies.push(IndexDiscrepancy::WrongSiblingIndex {
+                        child: *child,
+                        expected: expected_index,
+                        found: found.clone(),
+                    })
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    discrepancies.push(IndexDiscrepancy::MissingSiblingIndex { child: *child })
+                }
+            }
+        }
+    }
+
+    // parents left with children after all of them were unlinked
+    for (parent_id, parent_index) in v_parent_index.iter().with_id() {
+        if !expected.contains_key(&parent_id) && !parent_index.children.is_empty() {
+            discrepancies.push(IndexDiscrepancy::WrongChildren {
+                parent: parent_id,
+                expected: Vec::new(),
+                found: parent_index.children.iter().cloned().collect(),
+            });
+        }
+    }
+
+    let indexed_children = expected
+        .values()
+        .flatten()
+        .map(|(_, child)| *child)
+        .collect::<HashSet<_>>();
+    for (child, _) in v_sibling_index.iter().with_id() {
+        if !indexed_children.contains(&child) {
+            discrepancies.push(IndexDiscrepancy::UnexpectedSiblingIndex { child });
+        }
+    }
+
+    discrepancies
+}
+
+/// Throw away [ParentIndex], [SiblingIndex], [Depth] and [TreeRoot] and rebuild them from [ChildOf].
+///
+/// Every root is marked [Reindexed], so derived values like [Inherited] are recomputed for the whole tree.
+pub fn tree_index_rebuilding<R: Relation>(
+    (
+        v_entities,
+        v_child_of,
+        mut vm_sibling_index,
+        mut vm_parent_index,
+        mut vm_depth,
+        mut vm_tree_root,
+        mut vm_reindexed,
+    ): (
+        EntitiesView,
+        View<ChildOf<R>>,
+        ViewMut<SiblingIndex<R>>,
+        ViewMut<ParentIndex<R>>,
+        ViewMut<Depth<R>>,
+        ViewMut<TreeRoot<R>>,
+        ViewMut<Reindexed<R>>,
+    ),
+) {
+    vm_sibling_index.clear();
+    vm_parent_index.clear();
+    vm_depth.clear();
+    vm_tree_root.clear();
+    vm_reindexed.clear();
+
+    let expected = expected_children(&v_child_of);
+    info!(parents = expected.len(), "rebuilding tree indexes");
+    for (parent_id, children) in expected.iter() {
+        for (idx, (_, child)) in children.iter().enumerate() {
+            v_entities.add_component(
+                *child,
+                &mut vm_sibling_index,
+                expected_sibling_index(*parent_id, children, idx),
+            );
+        }
+        if v_entities.is_alive(*parent_id) {
+            v_entities.add_component(
+                *parent_id,
+                &mut vm_parent_index,
+                ParentIndex::new(children.iter().cloned().collect()),
+            );
+        }
+    }
+
+    let mut refreshed = HashSet::new();
+    for (parent_id, children) in expected.iter() {
+        if vm_sibling_index.contains(*parent_id) {
+            continue;
+        }
+        // children of a deleted parent are the topmost indexed nodes of their tree
+        let roots = if v_entities.is_alive(*parent_id) {
+            vec![*parent_id]
+        } else {
+            children.iter().map(|(_, child)| *child).collect()
+        };
+        for root in roots {
+            v_entities.add_component(root, &mut vm_reindexed, Reindexed::default());
+            indexing::refresh_depth_and_root(
+                &v_entities,
+                &vm_sibling_index,
+                &vm_parent_index,
+                &mut vm_depth,
+                &mut vm_tree_root,
+                &mut refreshed,
+                root,
+            );
+        }
+    }
+}
+
+/// Logs and keeps in [IndexDiscrepancies] any difference found by [verify_tree_indexes] after [tree_indexing].
+pub fn tree_verifying<R: Relation>(
+    (mut discrepancies, v_entities, v_child_of, v_parent_index, v_sibling_index): (
+        UniqueViewMut<IndexDiscrepancies<R>>,
+        EntitiesView,
+        View<ChildOf<R>>,
+        View<ParentIndex<R>>,
+        View<SiblingIndex<R>>,
+    ),
+) {
+    let found = verify_tree_indexes((v_entities, v_child_of, v_parent_index, v_sibling_index));
+    for discrepancy in found.iter() {
+        error!(?discrepancy, "tree indexes out of sync with ChildOf");
+    }
+    **discrepancies = found;
+}
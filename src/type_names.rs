@@ -18,6 +18,16 @@ impl TypeNames {
         type_id
     }
 
+    /// Store the type name for a type id which was looked up elsewhere
+    pub(crate) fn track_name(&self, type_id: TypeId, type_name: &'static str) -> TypeId {
+        self.0
+            .try_write()
+            .expect("all mine!")
+            .entry(type_id)
+            .or_insert(type_name);
+        type_id
+    }
+
     pub fn lookup_name(&self, type_id: &TypeId) -> Option<&'static str> {
         self.0
             .try_read()